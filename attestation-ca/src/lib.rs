@@ -1,11 +1,17 @@
-use base64urlsafedata::Base64UrlSafeData;
+use base64urlsafedata::{Base64UrlSafeData, HumanBinaryData};
 use openssl::error::ErrorStack as OpenSSLErrorStack;
-use openssl::{hash, x509};
+use openssl::nid::Nid;
+use openssl::{hash, pkey, x509};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
 use uuid::Uuid;
 
+pub use serde_cbor_2::Error as CborError;
+
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct DeviceDescription {
     pub(crate) en: String,
@@ -26,12 +32,42 @@ impl DeviceDescription {
     }
 }
 
+/// The kind of public key held by an Attestation CA.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicKeyKind {
+    /// An RSA key of the given modulus size.
+    Rsa { bits: u32 },
+    /// An elliptic curve key on the named curve, such as `prime256v1`.
+    Ecdsa { curve: String },
+    /// Any other key type, by name.
+    Other(String),
+}
+
+fn assume_true() -> bool {
+    true
+}
+
+/// The first entry of the subject name of a certificate with this `nid`, such as
+/// [Nid::COMMONNAME]. An empty entry is treated as absent.
+pub fn x509_subject_entry(x509: &x509::X509Ref, nid: Nid) -> Option<String> {
+    x509.subject_name()
+        .entries_by_nid(nid)
+        .next()
+        .and_then(|entry| entry.data().as_utf8().ok())
+        .map(|value| value.to_string())
+        .filter(|value| !value.is_empty())
+}
+
 /// A serialised Attestation CA.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SerialisableAttestationCa {
-    pub(crate) ca: Base64UrlSafeData,
+    pub(crate) ca: HumanBinaryData,
     pub(crate) aaguids: BTreeMap<Uuid, DeviceDescription>,
+    /// If this CA trusts any AAGUID it has signed. This is required, so that an empty set of
+    /// aaguids never implies a blanket allow.
     pub(crate) blanket_allow: bool,
+    #[serde(default = "assume_true")]
+    pub(crate) strict_eligible: bool,
 }
 
 /// A structure representing an Attestation CA and other options associated to this CA.
@@ -51,15 +87,19 @@ pub struct AttestationCa {
     /// this CA will NOT be trusted.
     aaguids: BTreeMap<Uuid, DeviceDescription>,
     blanket_allow: bool,
+    /// If this CA meets the requirements of a strict trust policy. CAs may not be eligible
+    /// due to weak signatures, or low quality hardware.
+    strict_eligible: bool,
 }
 
 #[allow(clippy::from_over_into)]
 impl Into<SerialisableAttestationCa> for AttestationCa {
     fn into(self) -> SerialisableAttestationCa {
         SerialisableAttestationCa {
-            ca: Base64UrlSafeData(self.ca.to_der().expect("Invalid DER")),
+            ca: self.ca.to_der().expect("Invalid DER").into(),
             aaguids: self.aaguids,
             blanket_allow: self.blanket_allow,
+            strict_eligible: self.strict_eligible,
         }
     }
 }
@@ -69,9 +109,10 @@ impl TryFrom<SerialisableAttestationCa> for AttestationCa {
 
     fn try_from(data: SerialisableAttestationCa) -> Result<Self, Self::Error> {
         Ok(AttestationCa {
-            ca: x509::X509::from_der(&data.ca.0)?,
+            ca: x509::X509::from_der(&data.ca)?,
             aaguids: data.aaguids,
             blanket_allow: data.blanket_allow,
+            strict_eligible: data.strict_eligible,
         })
     }
 }
@@ -89,6 +130,56 @@ impl AttestationCa {
         self.blanket_allow
     }
 
+    /// If this CA may be used in a strict trust policy.
+    pub fn strict_eligible(&self) -> bool {
+        self.strict_eligible
+    }
+
+    /// The DER encoding of this CA's certificate.
+    pub fn to_der(&self) -> Result<Vec<u8>, OpenSSLErrorStack> {
+        self.ca.to_der()
+    }
+
+    /// The PEM encoding of this CA's certificate.
+    pub fn to_pem(&self) -> Result<Vec<u8>, OpenSSLErrorStack> {
+        self.ca.to_pem()
+    }
+
+    /// The common name of the subject of this Attestation Ca, if present.
+    pub fn subject_common_name(&self) -> Option<String> {
+        x509_subject_entry(&self.ca, Nid::COMMONNAME)
+    }
+
+    /// The organization of the subject of this Attestation Ca, if present.
+    pub fn subject_organization(&self) -> Option<String> {
+        x509_subject_entry(&self.ca, Nid::ORGANIZATIONNAME)
+    }
+
+    /// Determine the type and size of the public key of this Attestation Ca
+    pub fn public_key_algorithm(&self) -> Result<PublicKeyKind, OpenSSLErrorStack> {
+        let pkey = self.ca.public_key()?;
+        match pkey.id() {
+            pkey::Id::RSA => Ok(PublicKeyKind::Rsa { bits: pkey.bits() }),
+            pkey::Id::EC => {
+                let curve = pkey
+                    .ec_key()?
+                    .group()
+                    .curve_name()
+                    .and_then(|nid| nid.short_name().ok())
+                    .unwrap_or("unknown")
+                    .to_string();
+                Ok(PublicKeyKind::Ecdsa { curve })
+            }
+            id => {
+                let name = Nid::from_raw(id.as_raw())
+                    .short_name()
+                    .map(str::to_string)
+                    .unwrap_or_else(|_| format!("{:?}", id));
+                Ok(PublicKeyKind::Other(name))
+            }
+        }
+    }
+
     /// Retrieve the Key Identifier for this Attestation Ca
     pub fn get_kid(&self) -> Result<Vec<u8>, OpenSSLErrorStack> {
         self.ca
@@ -96,6 +187,11 @@ impl AttestationCa {
             .map(|bytes| bytes.to_vec())
     }
 
+    /// Retrieve the Key Identifier for this Attestation Ca as a lowercase hex string
+    pub fn get_kid_hex(&self) -> Result<String, OpenSSLErrorStack> {
+        self.get_kid().map(hex::encode)
+    }
+
     fn insert_device(
         &mut self,
         aaguid: Uuid,
@@ -117,10 +213,30 @@ impl AttestationCa {
             ca: x509::X509::from_pem(data)?,
             aaguids: BTreeMap::default(),
             blanket_allow: true,
+            strict_eligible: true,
         })
     }
 
+    /// Create an Attestation Ca from a DER certificate. This CA will trust any AAGUID
+    /// that it has signed.
+    pub fn new_from_der(data: &[u8]) -> Result<Self, OpenSSLErrorStack> {
+        Ok(AttestationCa {
+            ca: x509::X509::from_der(data)?,
+            aaguids: BTreeMap::default(),
+            blanket_allow: true,
+            strict_eligible: true,
+        })
+    }
+
+    /// Start building an Attestation Ca from a DER certificate. If no AAGUIDs are added to
+    /// the builder, the resulting CA will trust any AAGUID that it has signed.
+    pub fn builder(der: &[u8]) -> Result<AttestationCaBuilder, OpenSSLErrorStack> {
+        AttestationCa::new_from_der(der).map(|att_ca| AttestationCaBuilder { att_ca })
+    }
+
     fn union(&mut self, other: &Self) {
+        // If either side considers this CA ineligible, that wins.
+        self.strict_eligible &= other.strict_eligible;
         // if either is a blanket allow, we just do that.
         if self.blanket_allow || other.blanket_allow {
             self.blanket_allow = true;
@@ -137,6 +253,7 @@ impl AttestationCa {
     }
 
     fn intersection(&mut self, other: &Self) {
+        self.strict_eligible &= other.strict_eligible;
         // If they are a blanket allow, do nothing, we are already
         // more restrictive, or we also are a blanket allow
         if other.blanket_allow() {
@@ -158,13 +275,99 @@ impl AttestationCa {
     }
 }
 
+/// A builder for an [AttestationCa] that is restricted to a set of AAGUIDs.
+pub struct AttestationCaBuilder {
+    att_ca: AttestationCa,
+}
+
+impl AttestationCaBuilder {
+    /// Restrict this CA to trust `aaguid`. The device has no description.
+    pub fn aaguid(mut self, aaguid: Uuid) -> Self {
+        self.att_ca
+            .insert_device(aaguid, String::default(), BTreeMap::default());
+        self
+    }
+
+    /// Restrict this CA to trust each of `aaguids`.
+    pub fn aaguids(self, aaguids: impl IntoIterator<Item = Uuid>) -> Self {
+        aaguids
+            .into_iter()
+            .fold(self, |builder, aaguid| builder.aaguid(aaguid))
+    }
+
+    /// Set if this CA may be used in a strict trust policy. Defaults to `true`.
+    pub fn strict_eligible(mut self, strict_eligible: bool) -> Self {
+        self.att_ca.strict_eligible = strict_eligible;
+        self
+    }
+
+    /// Complete the Attestation Ca.
+    pub fn build(self) -> AttestationCa {
+        self.att_ca
+    }
+}
+
+/// A precomputed mapping of AAGUIDs to the key identifiers of the CAs that trust them.
+#[derive(Debug, Default, Clone)]
+struct AaguidIndex {
+    aaguids: BTreeMap<Uuid, Vec<Base64UrlSafeData>>,
+    blanket_allow: Vec<Base64UrlSafeData>,
+}
+
+/// A serialised Attestation CA List.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerialisableAttestationCaList {
+    pub(crate) cas: BTreeMap<HumanBinaryData, AttestationCa>,
+}
+
 /// A list of AttestationCas and associated options.
-#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(
+    from = "SerialisableAttestationCaList",
+    into = "SerialisableAttestationCaList"
+)]
 pub struct AttestationCaList {
     /// The set of CA's that we trust in this Operation
     cas: BTreeMap<Base64UrlSafeData, AttestationCa>,
+    /// An optional index over cas, which is dropped whenever cas changes.
+    aaguid_index: Option<AaguidIndex>,
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<SerialisableAttestationCaList> for AttestationCaList {
+    fn into(self) -> SerialisableAttestationCaList {
+        SerialisableAttestationCaList {
+            cas: self
+                .cas
+                .into_iter()
+                .map(|(kid, att_ca)| (kid.into(), att_ca))
+                .collect(),
+        }
+    }
+}
+
+impl From<SerialisableAttestationCaList> for AttestationCaList {
+    fn from(data: SerialisableAttestationCaList) -> Self {
+        AttestationCaList {
+            cas: data
+                .cas
+                .into_iter()
+                .map(|(kid, att_ca)| (kid.into(), att_ca))
+                .collect(),
+            aaguid_index: None,
+        }
+    }
+}
+
+// The index is derived from cas, so it does not participate in equality.
+impl PartialEq for AttestationCaList {
+    fn eq(&self, other: &Self) -> bool {
+        self.cas == other.cas
+    }
 }
 
+impl Eq for AttestationCaList {}
+
 impl TryFrom<&[u8]> for AttestationCaList {
     type Error = OpenSSLErrorStack;
 
@@ -182,18 +385,119 @@ impl AttestationCaList {
     }
 
     pub fn clear(&mut self) {
+        self.aaguid_index = None;
         self.cas.clear()
     }
 
+    /// The number of Attestation Cas in this list.
     pub fn len(&self) -> usize {
         self.cas.len()
     }
 
+    /// Iterate over the Attestation Cas in this list, along with their key identifiers.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &AttestationCa)> {
+        self.cas
+            .iter()
+            .map(|(kid, att_ca)| (kid.as_slice(), att_ca))
+    }
+
     /// Determine if this attestation list contains any members.
     pub fn is_empty(&self) -> bool {
         self.cas.is_empty()
     }
 
+    /// Determine if an Attestation Ca with this key identifier, as produced by
+    /// [AttestationCa::get_kid], is in this list.
+    pub fn contains_kid(&self, kid: &[u8]) -> bool {
+        self.cas.contains_key(kid)
+    }
+
+    /// Retrieve the Attestation Ca with this key identifier, as produced by
+    /// [AttestationCa::get_kid].
+    pub fn get_by_kid(&self, kid: &[u8]) -> Option<&AttestationCa> {
+        self.cas.get(kid)
+    }
+
+    /// Retrieve the Attestation Ca with this hex encoded key identifier, as produced by
+    /// [AttestationCa::get_kid_hex]. Either case is accepted.
+    pub fn get_by_kid_hex(&self, kid: &str) -> Option<&AttestationCa> {
+        hex::decode(kid).ok().and_then(|kid| self.get_by_kid(&kid))
+    }
+
+    /// Create a copy of this list containing only the CAs that are eligible for a strict trust
+    /// policy. Verifying against this list skips CAs that are not eligible.
+    pub fn strict(&self) -> Self {
+        AttestationCaList {
+            cas: self
+                .cas
+                .iter()
+                .filter(|(_, att_ca)| att_ca.strict_eligible)
+                .map(|(kid, att_ca)| (kid.clone(), att_ca.clone()))
+                .collect(),
+            aaguid_index: None,
+        }
+    }
+
+    /// Precompute an index from AAGUIDs to the CAs that trust them, so that
+    /// [AttestationCaList::get_by_aaguid] does not need to scan every CA. The index is
+    /// discarded when the list is modified, and must be rebuilt after.
+    pub fn build_index(&mut self) {
+        let mut index = AaguidIndex::default();
+        for (kid, att_ca) in self.cas.iter() {
+            if att_ca.blanket_allow {
+                index.blanket_allow.push(kid.clone());
+            } else {
+                for aaguid in att_ca.aaguids.keys() {
+                    index.aaguids.entry(*aaguid).or_default().push(kid.clone());
+                }
+            }
+        }
+        self.aaguid_index = Some(index);
+    }
+
+    /// Determine if this list currently has an index built by
+    /// [AttestationCaList::build_index].
+    pub fn is_indexed(&self) -> bool {
+        self.aaguid_index.is_some()
+    }
+
+    /// Retrieve the set of Attestation Cas that would trust a device with this AAGUID. This
+    /// includes CAs that trust any AAGUID.
+    pub fn get_by_aaguid(&self, aaguid: &Uuid) -> Vec<&AttestationCa> {
+        match &self.aaguid_index {
+            Some(index) => {
+                let mut kids: Vec<_> = index
+                    .aaguids
+                    .get(aaguid)
+                    .into_iter()
+                    .flatten()
+                    .chain(index.blanket_allow.iter())
+                    .collect();
+                kids.sort_unstable();
+                kids.into_iter()
+                    .filter_map(|kid| self.cas.get(kid))
+                    .collect()
+            }
+            None => self
+                .cas
+                .values()
+                .filter(|att_ca| att_ca.blanket_allow || att_ca.aaguids.contains_key(aaguid))
+                .collect(),
+        }
+    }
+
+    /// Serialise this list to CBOR. This uses the same structure as the json form, but
+    /// certificates and key identifiers are stored as raw bytes rather than base64, so it is
+    /// more compact for embedding.
+    pub fn to_cbor(&self) -> Result<Vec<u8>, CborError> {
+        serde_cbor_2::to_vec(self)
+    }
+
+    /// Deserialise a list that was serialised with [AttestationCaList::to_cbor].
+    pub fn from_cbor(data: &[u8]) -> Result<Self, CborError> {
+        serde_cbor_2::from_slice(data)
+    }
+
     /// Insert a new att_ca into this Attestation Ca List
     pub fn insert(
         &mut self,
@@ -201,11 +505,13 @@ impl AttestationCaList {
     ) -> Result<Option<AttestationCa>, OpenSSLErrorStack> {
         // Get the key id (kid, digest).
         let att_ca_dgst = att_ca.get_kid()?;
+        self.aaguid_index = None;
         Ok(self.cas.insert(att_ca_dgst.into(), att_ca))
     }
 
     /// Join two CA lists into one, taking all elements from both.
     pub fn union(&mut self, other: &Self) {
+        self.aaguid_index = None;
         for (o_kid, o_att_ca) in other.cas.iter() {
             if let Some(s_att_ca) = self.cas.get_mut(o_kid) {
                 s_att_ca.union(o_att_ca)
@@ -217,6 +523,7 @@ impl AttestationCaList {
 
     /// Retain only the CA's and devices that exist in self and other.
     pub fn intersection(&mut self, other: &Self) {
+        self.aaguid_index = None;
         self.cas.retain(|s_kid, s_att_ca| {
             // First, does this exist in our partner?
             if let Some(o_att_ca) = other.cas.get(s_kid) {
@@ -237,6 +544,54 @@ impl AttestationCaList {
     }
 }
 
+impl AttestationCaList {
+    /// Collect a set of Attestation Cas into a list, merging CAs that share a key identifier.
+    /// Unlike the [FromIterator] implementation, this fails if a key identifier can not be
+    /// computed.
+    pub fn try_from_iter<I>(iter: I) -> Result<Self, OpenSSLErrorStack>
+    where
+        I: IntoIterator<Item = AttestationCa>,
+    {
+        let mut att_ca_builder = AttestationCaListBuilder::new();
+        for att_ca in iter {
+            att_ca_builder.insert_att_ca(att_ca)?;
+        }
+        Ok(att_ca_builder.build())
+    }
+}
+
+impl FromIterator<AttestationCa> for AttestationCaList {
+    /// Collect a set of Attestation Cas, merging CAs that share a key identifier. CAs whose key
+    /// identifier can not be computed are skipped.
+    fn from_iter<I: IntoIterator<Item = AttestationCa>>(iter: I) -> Self {
+        let mut att_ca_builder = AttestationCaListBuilder::new();
+        for att_ca in iter {
+            if let Err(err) = att_ca_builder.insert_att_ca(att_ca) {
+                tracing::error!(?err, "Failed to add attestation ca to list, skipping");
+            }
+        }
+        att_ca_builder.build()
+    }
+}
+
+impl FromIterator<(x509::X509, Uuid)> for AttestationCaList {
+    /// Collect a set of CAs and the AAGUIDs they trust. The devices have no description.
+    fn from_iter<I: IntoIterator<Item = (x509::X509, Uuid)>>(iter: I) -> Self {
+        iter.into_iter()
+            .map(|(ca, aaguid)| {
+                let mut att_ca = AttestationCa {
+                    ca,
+                    aaguids: BTreeMap::default(),
+                    blanket_allow: false,
+                    strict_eligible: true,
+                };
+                att_ca.insert_device(aaguid, String::default(), BTreeMap::default());
+                att_ca
+            })
+            .collect()
+    }
+}
+
 #[derive(Default)]
 pub struct AttestationCaListBuilder {
     cas: BTreeMap<Vec<u8>, AttestationCa>,
@@ -265,6 +620,7 @@ impl AttestationCaListBuilder {
                 ca,
                 aaguids: BTreeMap::default(),
                 blanket_allow: false,
+                strict_eligible: true,
             }
         };
 
@@ -275,6 +631,20 @@ impl AttestationCaListBuilder {
         Ok(())
     }
 
+    /// Insert an Attestation Ca into this builder. If a CA with the same key identifier has
+    /// already been inserted the two are merged, taking the union of their AAGUIDs.
+    pub fn insert_att_ca(&mut self, att_ca: AttestationCa) -> Result<(), OpenSSLErrorStack> {
+        let kid = att_ca.get_kid()?;
+
+        if let Some(s_att_ca) = self.cas.get_mut(&kid) {
+            s_att_ca.union(&att_ca);
+        } else {
+            self.cas.insert(kid, att_ca);
+        }
+
+        Ok(())
+    }
+
     pub fn insert_device_der(
         &mut self,
         ca_der: &[u8],
@@ -304,6 +674,388 @@ impl AttestationCaListBuilder {
             .map(|(kid, att_ca)| (kid.into(), att_ca))
             .collect();
 
-        AttestationCaList { cas }
+        AttestationCaList {
+            cas,
+            aaguid_index: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testkit::{generate_test_ca, issue_leaf, TestCaBuilder};
+
+    #[test]
+    fn test_public_key_algorithm() {
+        let ec_ca = generate_test_ca().0;
+        assert_eq!(
+            ec_ca.public_key_algorithm().unwrap(),
+            PublicKeyKind::Ecdsa {
+                curve: "prime256v1".to_string()
+            }
+        );
+
+        let rsa_ca = TestCaBuilder::new().rsa().build().0;
+        assert_eq!(
+            rsa_ca.public_key_algorithm().unwrap(),
+            PublicKeyKind::Rsa { bits: 2048 }
+        );
+    }
+
+    #[test]
+    fn test_subject_names() {
+        let (ca, _) = TestCaBuilder::new()
+            .common_name(Some("Webauthn Test CA"))
+            .build();
+        assert_eq!(
+            ca.subject_common_name().as_deref(),
+            Some("Webauthn Test CA")
+        );
+        assert_eq!(
+            ca.subject_organization().as_deref(),
+            Some("Webauthn Attestation CA")
+        );
+
+        let (ca, _) = TestCaBuilder::new().common_name(None).build();
+        assert_eq!(ca.subject_common_name(), None);
+        assert_eq!(
+            ca.subject_organization().as_deref(),
+            Some("Webauthn Attestation CA")
+        );
+
+        // An empty common name is treated as absent.
+        let (ca, _) = TestCaBuilder::new().common_name(Some("")).build();
+        assert_eq!(ca.subject_common_name(), None);
+        assert_eq!(
+            ca.subject_organization().as_deref(),
+            Some("Webauthn Attestation CA")
+        );
+    }
+
+    #[test]
+    fn test_list_kid_lookup() {
+        let ca_a = generate_test_ca().0;
+        let ca_b = generate_test_ca().0;
+        let kid_a = ca_a.get_kid().unwrap();
+        let kid_b = ca_b.get_kid().unwrap();
+
+        let mut list = AttestationCaList::default();
+        assert!(list.insert(ca_a.clone()).unwrap().is_none());
+
+        assert!(list.contains_kid(&kid_a));
+        assert!(!list.contains_kid(&kid_b));
+        assert_eq!(list.get_by_kid(&kid_a), Some(&ca_a));
+        assert_eq!(list.get_by_kid(&kid_b), None);
+    }
+
+    #[test]
+    fn test_kid_hex() {
+        let ca = generate_test_ca().0;
+        let kid_hex = ca.get_kid_hex().unwrap();
+        assert_eq!(kid_hex, hex::encode(ca.get_kid().unwrap()));
+        assert_eq!(kid_hex, kid_hex.to_lowercase());
+
+        let mut list = AttestationCaList::default();
+        list.insert(ca.clone()).unwrap();
+
+        assert_eq!(list.get_by_kid_hex(&kid_hex), Some(&ca));
+        assert_eq!(list.get_by_kid_hex(&kid_hex.to_uppercase()), Some(&ca));
+        assert_eq!(list.get_by_kid_hex("not hex"), None);
+        assert_eq!(list.get_by_kid_hex("00"), None);
+    }
+
+    #[test]
+    fn test_list_cbor_round_trip() {
+        let aaguid = uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b");
+        let (ca, ca_key) = generate_test_ca();
+        let leaf = issue_leaf(&ca_key, aaguid);
+
+        let mut builder = AttestationCaListBuilder::new();
+        builder
+            .insert_device_x509(
+                ca.ca().clone(),
+                aaguid,
+                "Test Device".to_string(),
+                BTreeMap::from([("de".to_string(), "Testgerät".to_string())]),
+            )
+            .unwrap();
+        let list = builder.build();
+
+        let cbor = list.to_cbor().unwrap();
+        let restored = AttestationCaList::from_cbor(&cbor).unwrap();
+        assert_eq!(restored, list);
+
+        let (_, restored_ca) = restored.cas().iter().next().unwrap();
+        assert!(leaf
+            .verify(&restored_ca.ca().public_key().unwrap())
+            .unwrap());
+
+        assert!(AttestationCaList::from_cbor(&cbor[..cbor.len() - 1]).is_err());
+
+        // The certificate is embedded as raw DER, so the CBOR is smaller than the json.
+        let der = ca.to_der().unwrap();
+        assert!(cbor
+            .windows(der.len())
+            .any(|window| window == der.as_slice()));
+        let json = serde_json::to_vec(&list).unwrap();
+        assert!(cbor.len() < json.len());
+    }
+
+    #[test]
+    fn test_list_json_uses_base64() {
+        let (ca, _) = generate_test_ca();
+        let der = ca.to_der().unwrap();
+        let kid = ca.get_kid().unwrap();
+
+        let mut list = AttestationCaList::default();
+        list.insert(ca).unwrap();
+
+        let json = serde_json::to_value(&list).unwrap();
+        let kid = Base64UrlSafeData::from(kid).to_string();
+        assert_eq!(
+            json["cas"][kid.as_str()]["ca"],
+            serde_json::Value::String(Base64UrlSafeData::from(der).to_string())
+        );
+
+        let restored: AttestationCaList = serde_json::from_value(json).unwrap();
+        assert_eq!(restored, list);
+    }
+
+    #[test]
+    fn test_list_iter() {
+        let mut list = AttestationCaList::default();
+        assert_eq!(list.iter().count(), 0);
+
+        list.insert(generate_test_ca().0).unwrap();
+        list.insert(TestCaBuilder::new().rsa().build().0).unwrap();
+
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.iter().count(), 2);
+        for (kid, att_ca) in list.iter() {
+            assert_eq!(kid, att_ca.get_kid().unwrap().as_slice());
+        }
+    }
+
+    #[test]
+    fn test_att_ca_builder() {
+        let der = generate_test_ca().0.to_der().unwrap();
+        let aaguid_a = uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b");
+        let aaguid_b = uuid::uuid!("d8522d9f-575b-4866-88a9-ba99fa02f35b");
+
+        let ca = AttestationCa::builder(&der).unwrap().build();
+        assert!(ca.blanket_allow());
+        assert!(ca.aaguids().is_empty());
+
+        let ca = AttestationCa::builder(&der)
+            .unwrap()
+            .aaguid(aaguid_a)
+            .aaguids([aaguid_b, aaguid_a])
+            .build();
+        assert!(!ca.blanket_allow());
+        assert_eq!(
+            ca.aaguids().keys().copied().collect::<Vec<_>>(),
+            vec![aaguid_a, aaguid_b]
+        );
+        assert_eq!(ca.ca(), AttestationCa::new_from_der(&der).unwrap().ca());
+
+        assert!(AttestationCa::builder(&der[1..]).is_err());
+    }
+
+    #[test]
+    fn test_list_aaguid_index() {
+        let aaguid_a = uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b");
+        let aaguid_b = uuid::uuid!("d8522d9f-575b-4866-88a9-ba99fa02f35b");
+        let aaguid_c = uuid::uuid!("2fc0579f-8113-47ea-b116-bb5a8db9202a");
+
+        let der_a = generate_test_ca().0.to_der().unwrap();
+        let der_b = generate_test_ca().0.to_der().unwrap();
+
+        let mut list = AttestationCaList::default();
+        list.insert(
+            AttestationCa::builder(&der_a)
+                .unwrap()
+                .aaguid(aaguid_a)
+                .build(),
+        )
+        .unwrap();
+        list.insert(
+            AttestationCa::builder(&der_b)
+                .unwrap()
+                .aaguids([aaguid_a, aaguid_b])
+                .build(),
+        )
+        .unwrap();
+        let blanket = generate_test_ca().0;
+        list.insert(blanket.clone()).unwrap();
+
+        let scanned: Vec<_> = [aaguid_a, aaguid_b, aaguid_c]
+            .iter()
+            .map(|aaguid| list.get_by_aaguid(aaguid))
+            .map(|cas| cas.into_iter().cloned().collect::<Vec<_>>())
+            .collect();
+        assert_eq!(scanned[0].len(), 3);
+        assert_eq!(scanned[1].len(), 2);
+        assert_eq!(scanned[2], vec![blanket]);
+
+        list.build_index();
+        assert!(list.is_indexed());
+
+        let indexed: Vec<_> = [aaguid_a, aaguid_b, aaguid_c]
+            .iter()
+            .map(|aaguid| list.get_by_aaguid(aaguid))
+            .map(|cas| cas.into_iter().cloned().collect::<Vec<_>>())
+            .collect();
+        assert_eq!(indexed, scanned);
+
+        // Bypass invalidation to show the index, not a scan, serves the lookup.
+        let extra = AttestationCa::builder(&generate_test_ca().0.to_der().unwrap())
+            .unwrap()
+            .aaguid(aaguid_c)
+            .build();
+        list.cas
+            .insert(extra.get_kid().unwrap().into(), extra.clone());
+        assert_eq!(list.get_by_aaguid(&aaguid_c).len(), 1);
+
+        // Modifying the list through its api drops the index.
+        list.insert(extra).unwrap();
+        assert!(!list.is_indexed());
+        assert_eq!(list.get_by_aaguid(&aaguid_c).len(), 2);
+
+        // The index is not part of equality or serialisation.
+        let mut indexed_list = list.clone();
+        indexed_list.build_index();
+        assert_eq!(indexed_list, list);
+        let restored = AttestationCaList::from_cbor(&indexed_list.to_cbor().unwrap()).unwrap();
+        assert!(!restored.is_indexed());
+    }
+
+    #[test]
+    fn test_blanket_allow_serialisation() {
+        let der = generate_test_ca().0.to_der().unwrap();
+        let aaguid = uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b");
+
+        let allow_all = AttestationCa::builder(&der).unwrap().build();
+        let restricted = AttestationCa::builder(&der).unwrap().aaguid(aaguid).build();
+
+        for att_ca in [allow_all, restricted] {
+            let json = serde_json::to_value(&att_ca).unwrap();
+            assert_eq!(
+                json["blanket_allow"],
+                serde_json::Value::Bool(att_ca.blanket_allow())
+            );
+            let restored: AttestationCa = serde_json::from_value(json).unwrap();
+            assert_eq!(restored, att_ca);
+        }
+
+        // An explicit flag is kept as written, even with an empty set of aaguids.
+        let mut json = serde_json::to_value(AttestationCa::builder(&der).unwrap().build()).unwrap();
+        json["blanket_allow"] = serde_json::Value::Bool(false);
+        let restored: AttestationCa = serde_json::from_value(json.clone()).unwrap();
+        assert!(!restored.blanket_allow());
+
+        // A missing flag is rejected, rather than inferred from an empty set of aaguids.
+        json.as_object_mut().unwrap().remove("blanket_allow");
+        assert!(serde_json::from_value::<AttestationCa>(json).is_err());
+    }
+
+    #[test]
+    fn test_strict_eligible() {
+        let aaguid = uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b");
+        let eligible = generate_test_ca().0;
+        let ineligible =
+            AttestationCa::builder(&TestCaBuilder::new().rsa().build().0.to_der().unwrap())
+                .unwrap()
+                .strict_eligible(false)
+                .build();
+
+        let mut list = AttestationCaList::default();
+        list.insert(eligible.clone()).unwrap();
+        list.insert(ineligible.clone()).unwrap();
+
+        // Without strict, both CAs may be used.
+        assert_eq!(list.get_by_aaguid(&aaguid).len(), 2);
+
+        let strict = list.strict();
+        assert_eq!(strict.len(), 1);
+        assert_eq!(strict.get_by_aaguid(&aaguid), vec![&eligible]);
+        assert!(!strict.contains_kid(&ineligible.get_kid().unwrap()));
+
+        // The flag round trips, and defaults to eligible when absent.
+        let mut json = serde_json::to_value(&ineligible).unwrap();
+        let restored: AttestationCa = serde_json::from_value(json.clone()).unwrap();
+        assert!(!restored.strict_eligible());
+        json.as_object_mut().unwrap().remove("strict_eligible");
+        let restored: AttestationCa = serde_json::from_value(json).unwrap();
+        assert!(restored.strict_eligible());
+    }
+
+    #[test]
+    fn test_att_ca_encodings() {
+        let ca = generate_test_ca().0;
+
+        let der = ca.to_der().unwrap();
+        let restored = AttestationCa::new_from_der(&der).unwrap();
+        assert_eq!(restored, ca);
+        assert_eq!(restored.get_kid().unwrap(), ca.get_kid().unwrap());
+
+        let pem = ca.to_pem().unwrap();
+        assert!(pem.starts_with(b"-----BEGIN CERTIFICATE-----"));
+        assert_eq!(AttestationCa::new_from_pem(&pem).unwrap(), ca);
+    }
+
+    #[test]
+    fn test_list_from_iter() {
+        let aaguid_a = uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b");
+        let aaguid_b = uuid::uuid!("d8522d9f-575b-4866-88a9-ba99fa02f35b");
+
+        let der = generate_test_ca().0.to_der().unwrap();
+        let other = generate_test_ca().0;
+
+        let ca_a = AttestationCa::builder(&der)
+            .unwrap()
+            .aaguid(aaguid_a)
+            .build();
+        let ca_b = AttestationCa::builder(&der)
+            .unwrap()
+            .aaguid(aaguid_b)
+            .strict_eligible(false)
+            .build();
+        let kid = ca_a.get_kid().unwrap();
+
+        let list: AttestationCaList = [ca_a.clone(), ca_b.clone(), other.clone()]
+            .into_iter()
+            .collect();
+        assert_eq!(list.len(), 2);
+
+        let merged = list.get_by_kid(&kid).unwrap();
+        assert!(!merged.blanket_allow());
+        assert!(!merged.strict_eligible());
+        assert_eq!(
+            merged.aaguids().keys().copied().collect::<Vec<_>>(),
+            vec![aaguid_a, aaguid_b]
+        );
+        assert!(list
+            .get_by_kid(&other.get_kid().unwrap())
+            .unwrap()
+            .blanket_allow());
+
+        assert_eq!(
+            AttestationCaList::try_from_iter([ca_a.clone(), ca_b, other]).unwrap(),
+            list
+        );
+
+        // The tuple form routes through the same merge.
+        let list: AttestationCaList =
+            [(ca_a.ca().clone(), aaguid_a), (ca_a.ca().clone(), aaguid_b)]
+                .into_iter()
+                .collect();
+        assert_eq!(list.len(), 1);
+        let merged = list.get_by_kid(&kid).unwrap();
+        assert!(merged.strict_eligible());
+        assert_eq!(
+            merged.aaguids().keys().copied().collect::<Vec<_>>(),
+            vec![aaguid_a, aaguid_b]
+        );
     }
 }
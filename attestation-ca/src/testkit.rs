@@ -0,0 +1,360 @@
+//! Helpers to construct a minimal trust store for tests. This is only available to tests
+//! of this crate, or with the `testkit` feature enabled.
+//!
+//! ```ignore
+//! let (att_ca, ca_key) = generate_test_ca();
+//! let leaf = issue_leaf(&ca_key, aaguid);
+//! ```
+
+use crate::AttestationCa;
+use openssl::asn1::{Asn1Integer, Asn1Object, Asn1OctetString, Asn1Time, Asn1Type};
+use openssl::bn::{BigNum, MsbOption};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::nid::Nid;
+use openssl::rsa::Rsa;
+use openssl::x509::extension::{BasicConstraints, KeyUsage};
+use openssl::{hash, pkey, x509};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// The OID of the id-fido-gen-ce-aaguid extension.
+const FIDO_GEN_CE_AAGUID_OID: &str = "1.3.6.1.4.1.45724.1.1.4";
+const SUBJECT_KEY_IDENTIFIER_OID: &str = "2.5.29.14";
+const AUTHORITY_KEY_IDENTIFIER_OID: &str = "2.5.29.35";
+
+const TEST_CA_ORG: &str = "Webauthn Attestation CA";
+const TEST_CA_CN: &str = "Webauthn Test Attestation CA";
+
+fn ec_key() -> pkey::PKey<pkey::Private> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).expect("prime256v1 is supported");
+    let ec_key = EcKey::generate(&group).expect("failed to generate an ec key");
+    pkey::PKey::from_ec_key(ec_key).expect("failed to wrap an ec key")
+}
+
+fn rsa_key() -> pkey::PKey<pkey::Private> {
+    let rsa = Rsa::generate(2048).expect("failed to generate an rsa key");
+    pkey::PKey::from_rsa(rsa).expect("failed to wrap an rsa key")
+}
+
+fn random_serial() -> Asn1Integer {
+    let mut serial = BigNum::new().expect("failed to allocate a bignum");
+    serial
+        .rand(64, MsbOption::MAYBE_ZERO, false)
+        .expect("failed to generate a serial");
+    serial.to_asn1_integer().expect("failed to encode a serial")
+}
+
+fn ca_name(common_name: Option<&str>) -> x509::X509Name {
+    let mut x509_name = x509::X509NameBuilder::new().expect("failed to create a name");
+    x509_name
+        .append_entry_by_text("O", TEST_CA_ORG)
+        .expect("failed to set O");
+    if let Some(cn) = common_name {
+        // An explicit type skips the minimum length check, so that tests may use an empty CN.
+        x509_name
+            .append_entry_by_text_with_type("CN", cn, Asn1Type::UTF8STRING)
+            .expect("failed to set CN");
+    }
+    x509_name.build()
+}
+
+// Every test CA may share a subject name, so the CA and its leaves carry a key identifier
+// derived from the CA key. This lets a store holding several test CAs find the right issuer.
+// Any derivation is valid as long as the CA and leaf agree, so this hashes the whole
+// SubjectPublicKeyInfo.
+fn key_identifier(key: &pkey::PKeyRef<pkey::Private>) -> Vec<u8> {
+    let spki = key
+        .public_key_to_der()
+        .expect("failed to encode the public key");
+    hash::hash(hash::MessageDigest::sha1(), &spki)
+        .expect("failed to hash the public key")
+        .to_vec()
+}
+
+fn der_extension(oid: &str, der: &[u8]) -> x509::X509Extension {
+    x509::X509Extension::new_from_der(
+        &Asn1Object::from_str(oid).expect("invalid oid"),
+        false,
+        &Asn1OctetString::new_from_bytes(der).expect("failed to encode the extension"),
+    )
+    .expect("failed to build the extension")
+}
+
+fn subject_key_identifier(key: &pkey::PKeyRef<pkey::Private>) -> x509::X509Extension {
+    // OCTET STRING { keyIdentifier }
+    let mut der = vec![0x04, 0x14];
+    der.extend_from_slice(&key_identifier(key));
+    der_extension(SUBJECT_KEY_IDENTIFIER_OID, &der)
+}
+
+fn authority_key_identifier(ca_key: &pkey::PKeyRef<pkey::Private>) -> x509::X509Extension {
+    // SEQUENCE { [0] keyIdentifier }
+    let mut der = vec![0x30, 0x16, 0x80, 0x14];
+    der.extend_from_slice(&key_identifier(ca_key));
+    der_extension(AUTHORITY_KEY_IDENTIFIER_OID, &der)
+}
+
+/// A builder for a self signed test CA. By default the CA has an ECDSA P-256 key, and is
+/// named with the same subject as [generate_test_ca].
+pub struct TestCaBuilder {
+    rsa: bool,
+    common_name: Option<String>,
+}
+
+impl Default for TestCaBuilder {
+    fn default() -> Self {
+        TestCaBuilder {
+            rsa: false,
+            common_name: Some(TEST_CA_CN.to_string()),
+        }
+    }
+}
+
+impl TestCaBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use a 2048 bit RSA key rather than ECDSA P-256.
+    pub fn rsa(mut self) -> Self {
+        self.rsa = true;
+        self
+    }
+
+    /// Set the common name of the CA subject. `None` omits the common name, while `Some("")`
+    /// sets an empty one.
+    pub fn common_name(mut self, common_name: Option<&str>) -> Self {
+        self.common_name = common_name.map(str::to_string);
+        self
+    }
+
+    /// Generate the CA and its private key. The CA trusts any AAGUID that it has signed, and
+    /// is valid for one day.
+    pub fn build(self) -> (AttestationCa, pkey::PKey<pkey::Private>) {
+        let ca_key = if self.rsa { rsa_key() } else { ec_key() };
+        let ca_name = ca_name(self.common_name.as_deref());
+
+        let mut cert_builder = x509::X509::builder().expect("failed to create a certificate");
+        // Yes, 2 actually means 3 here ...
+        cert_builder.set_version(2).expect("failed to set version");
+        cert_builder
+            .set_serial_number(&random_serial())
+            .expect("failed to set serial");
+        cert_builder
+            .set_subject_name(&ca_name)
+            .expect("failed to set subject");
+        cert_builder
+            .set_issuer_name(&ca_name)
+            .expect("failed to set issuer");
+        cert_builder
+            .set_not_before(&Asn1Time::days_from_now(0).expect("invalid time"))
+            .expect("failed to set not before");
+        cert_builder
+            .set_not_after(&Asn1Time::days_from_now(1).expect("invalid time"))
+            .expect("failed to set not after");
+        cert_builder
+            .append_extension(
+                BasicConstraints::new()
+                    .critical()
+                    .ca()
+                    .build()
+                    .expect("failed to build basic constraints"),
+            )
+            .expect("failed to add basic constraints");
+        cert_builder
+            .append_extension(
+                KeyUsage::new()
+                    .critical()
+                    .key_cert_sign()
+                    .build()
+                    .expect("failed to build key usage"),
+            )
+            .expect("failed to add key usage");
+        cert_builder
+            .append_extension(subject_key_identifier(&ca_key))
+            .expect("failed to add the subject key identifier");
+        cert_builder.set_pubkey(&ca_key).expect("failed to set key");
+        cert_builder
+            .sign(&ca_key, hash::MessageDigest::sha256())
+            .expect("failed to sign the ca");
+
+        let att_ca = AttestationCa {
+            ca: cert_builder.build(),
+            aaguids: BTreeMap::default(),
+            blanket_allow: true,
+            strict_eligible: true,
+        };
+
+        (att_ca, ca_key)
+    }
+}
+
+/// Generate a self signed CA and its private key. The CA trusts any AAGUID that it has signed,
+/// and is valid for one day.
+pub fn generate_test_ca() -> (AttestationCa, pkey::PKey<pkey::Private>) {
+    TestCaBuilder::new().build()
+}
+
+/// Issue an attestation certificate for `aaguid`, signed by a CA key from
+/// [generate_test_ca]. The AAGUID is set in the id-fido-gen-ce-aaguid extension. For a CA
+/// from a [TestCaBuilder] with a different name, use [issue_leaf_for].
+pub fn issue_leaf(ca_key: &pkey::PKeyRef<pkey::Private>, aaguid: Uuid) -> x509::X509 {
+    sign_leaf(&ca_name(Some(TEST_CA_CN)), ca_key, aaguid).0
+}
+
+/// Issue an attestation certificate for `aaguid`, signed by `ca`. This also returns the
+/// private key of the leaf, so that tests can sign with it.
+pub fn issue_leaf_for(
+    ca: &AttestationCa,
+    ca_key: &pkey::PKeyRef<pkey::Private>,
+    aaguid: Uuid,
+) -> (x509::X509, pkey::PKey<pkey::Private>) {
+    sign_leaf(ca.ca().subject_name(), ca_key, aaguid)
+}
+
+fn sign_leaf(
+    issuer: &x509::X509NameRef,
+    ca_key: &pkey::PKeyRef<pkey::Private>,
+    aaguid: Uuid,
+) -> (x509::X509, pkey::PKey<pkey::Private>) {
+    let leaf_key = ec_key();
+
+    let mut x509_name = x509::X509NameBuilder::new().expect("failed to create a name");
+    x509_name
+        .append_entry_by_text("OU", "Authenticator Attestation")
+        .expect("failed to set OU");
+    x509_name
+        .append_entry_by_text("CN", "Webauthn Test Authenticator")
+        .expect("failed to set CN");
+    let x509_name = x509_name.build();
+
+    // The extension value is a DER OCTET STRING containing the 16 byte AAGUID.
+    let mut aaguid_der = vec![0x04, 0x10];
+    aaguid_der.extend_from_slice(aaguid.as_bytes());
+
+    let mut cert_builder = x509::X509::builder().expect("failed to create a certificate");
+    cert_builder.set_version(2).expect("failed to set version");
+    cert_builder
+        .set_serial_number(&random_serial())
+        .expect("failed to set serial");
+    cert_builder
+        .set_subject_name(&x509_name)
+        .expect("failed to set subject");
+    cert_builder
+        .set_issuer_name(issuer)
+        .expect("failed to set issuer");
+    cert_builder
+        .set_not_before(&Asn1Time::days_from_now(0).expect("invalid time"))
+        .expect("failed to set not before");
+    cert_builder
+        .set_not_after(&Asn1Time::days_from_now(1).expect("invalid time"))
+        .expect("failed to set not after");
+    cert_builder
+        .append_extension(
+            BasicConstraints::new()
+                .critical()
+                .build()
+                .expect("failed to build basic constraints"),
+        )
+        .expect("failed to add basic constraints");
+    cert_builder
+        .append_extension(authority_key_identifier(ca_key))
+        .expect("failed to add the authority key identifier");
+    cert_builder
+        .append_extension(der_extension(FIDO_GEN_CE_AAGUID_OID, &aaguid_der))
+        .expect("failed to add the aaguid extension");
+    cert_builder
+        .set_pubkey(&leaf_key)
+        .expect("failed to set key");
+    cert_builder
+        .sign(ca_key, hash::MessageDigest::sha256())
+        .expect("failed to sign the leaf");
+
+    (cert_builder.build(), leaf_key)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use openssl::stack::Stack;
+    use openssl::x509::store::X509StoreBuilder;
+    use openssl::x509::X509StoreContext;
+
+    fn verifies(cas: &[&AttestationCa], leaf: &x509::X509) -> bool {
+        let mut store = X509StoreBuilder::new().unwrap();
+        for ca in cas {
+            store.add_cert(ca.ca().clone()).unwrap();
+        }
+        let store = store.build();
+
+        let mut ctx = X509StoreContext::new().unwrap();
+        let chain = Stack::new().unwrap();
+        ctx.init(&store, leaf, &chain, |ctx| ctx.verify_cert())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_issued_leaf_verifies() {
+        let aaguid = uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b");
+        let (att_ca, ca_key) = generate_test_ca();
+        let leaf = issue_leaf(&ca_key, aaguid);
+
+        assert!(att_ca.blanket_allow());
+        assert_eq!(att_ca.subject_common_name().as_deref(), Some(TEST_CA_CN));
+        assert!(leaf.verify(&att_ca.ca().public_key().unwrap()).unwrap());
+        assert!(verifies(&[&att_ca], &leaf));
+
+        // The aaguid is carried in the leaf as the DER of an OCTET STRING.
+        let leaf_der = leaf.to_der().unwrap();
+        let mut aaguid_der = vec![0x04, 0x10];
+        aaguid_der.extend_from_slice(aaguid.as_bytes());
+        assert!(leaf_der
+            .windows(aaguid_der.len())
+            .any(|window| window == aaguid_der.as_slice()));
+
+        // A leaf from an unrelated CA does not verify.
+        let (_, other_key) = generate_test_ca();
+        let other_leaf = issue_leaf(&other_key, aaguid);
+        assert!(!other_leaf
+            .verify(&att_ca.ca().public_key().unwrap())
+            .unwrap());
+        assert!(!verifies(&[&att_ca], &other_leaf));
+    }
+
+    #[test]
+    fn test_same_name_cas_in_one_store() {
+        let aaguid = uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b");
+        let (ca_a, key_a) = generate_test_ca();
+        let (ca_b, key_b) = generate_test_ca();
+        assert_eq!(
+            ca_a.ca().subject_name().to_der().unwrap(),
+            ca_b.ca().subject_name().to_der().unwrap()
+        );
+
+        // The key identifiers select the right issuer regardless of store order.
+        let leaf_a = issue_leaf(&key_a, aaguid);
+        let leaf_b = issue_leaf(&key_b, aaguid);
+        assert!(verifies(&[&ca_a, &ca_b], &leaf_a));
+        assert!(verifies(&[&ca_a, &ca_b], &leaf_b));
+        assert!(verifies(&[&ca_b, &ca_a], &leaf_a));
+        assert!(verifies(&[&ca_b, &ca_a], &leaf_b));
+    }
+
+    #[test]
+    fn test_builder_ca() {
+        let aaguid = uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b");
+        let (ca, ca_key) = TestCaBuilder::new()
+            .rsa()
+            .common_name(Some("RSA CA"))
+            .build();
+        assert_eq!(ca.subject_common_name().as_deref(), Some("RSA CA"));
+        assert!(ca_key.rsa().is_ok());
+
+        let (leaf, leaf_key) = issue_leaf_for(&ca, &ca_key, aaguid);
+        assert!(verifies(&[&ca], &leaf));
+        assert!(leaf.public_key().unwrap().public_eq(&leaf_key));
+
+        let (ca, _) = TestCaBuilder::new().common_name(None).build();
+        assert_eq!(ca.subject_common_name(), None);
+    }
+}
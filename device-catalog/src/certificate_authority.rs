@@ -2,12 +2,22 @@
 
 use crate::prelude::*;
 
+use openssl::nid::Nid;
 use std::fmt;
+use webauthn_attestation_ca::x509_subject_entry;
 
 pub struct Authority {
     pub ca: x509::X509,
 }
 
+impl Authority {
+    /// The common name of the subject of this CA, if present. An empty common name is treated
+    /// as absent, as with `AttestationCa::subject_common_name`.
+    pub fn common_name(&self) -> Option<String> {
+        x509_subject_entry(&self.ca, Nid::COMMONNAME)
+    }
+}
+
 impl fmt::Debug for Authority {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Authority")
@@ -15,3 +25,25 @@ impl fmt::Debug for Authority {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use webauthn_attestation_ca::testkit::TestCaBuilder;
+
+    #[test]
+    fn test_common_name() {
+        for (cn, expected) in [
+            (Some("Webauthn Test CA"), Some("Webauthn Test CA")),
+            (Some(""), None),
+            (None, None),
+        ] {
+            let (att_ca, _) = TestCaBuilder::new().common_name(cn).build();
+            let authority = Authority {
+                ca: att_ca.ca().clone(),
+            };
+            assert_eq!(authority.common_name().as_deref(), expected);
+            assert_eq!(authority.common_name(), att_ca.subject_common_name());
+        }
+    }
+}
@@ -41,6 +41,7 @@ impl DataBuilder {
 
         let yk_5_fips_sku_5_4_3 = Rc::new(Sku {
             display_name: "YubiKey 5 FIPS Series".to_string(),
+            display_names: BTreeMap::default(),
             version: "5.4.3".to_string(),
         });
 
@@ -64,6 +65,7 @@ impl DataBuilder {
 
         let yk_5_bio_sku_5_5_6 = Rc::new(Sku {
             display_name: "YubiKey Bio Series".to_string(),
+            display_names: BTreeMap::default(),
             version: "5.5.6".to_string(),
         });
 
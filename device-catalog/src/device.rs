@@ -2,7 +2,7 @@ use crate::aaguid::Aaguid;
 use crate::image::Image;
 use crate::manufacturer::Manufacturer;
 use crate::quirks::Quirk;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::query::Query;
 use std::rc::Rc;
@@ -21,11 +21,52 @@ pub struct Device {
 #[derive(Debug)]
 pub struct Sku {
     pub display_name: String,
+    /// Localised display names, keyed by locale. `display_name` remains the canonical name.
+    pub display_names: BTreeMap<String, String>,
     pub version: String,
 }
 
+impl Sku {
+    /// The display name for `locale`, falling back to `display_name` if there is no
+    /// localised name.
+    pub fn display_name_for(&self, locale: &str) -> &str {
+        self.display_names
+            .get(locale)
+            .map(String::as_str)
+            .unwrap_or(self.display_name.as_str())
+    }
+}
+
 impl Device {
-    pub(crate) fn query_match(&self, _q: &Query) -> bool {
-        todo!();
+    pub(crate) fn query_match(&self, q: &Query) -> bool {
+        match q {
+            Query::AaguidEqual(u) => self.aaguid.id == *u,
+            Query::AaguidNotEqual(u) => self.aaguid.id != *u,
+            Query::DisplayNameEqual(s) => self.skus.iter().any(|sku| sku.display_name == *s),
+            Query::DisplayNameNotEqual(s) => self.skus.iter().all(|sku| sku.display_name != *s),
+            Query::DisplayNameContains(s) => {
+                let s = s.to_lowercase();
+                self.skus
+                    .iter()
+                    .any(|sku| sku.display_name.to_lowercase().contains(s.as_str()))
+            }
+            Query::CaEqual(s) => self.ca_names().any(|cn| cn == *s),
+            Query::CaNotEqual(s) => self.ca_names().all(|cn| cn != *s),
+            Query::CaContains(s) => {
+                let s = s.to_lowercase();
+                self.ca_names()
+                    .any(|cn| cn.to_lowercase().contains(s.as_str()))
+            }
+            Query::And(a, b) => self.query_match(a) && self.query_match(b),
+            Query::Or(a, b) => self.query_match(a) || self.query_match(b),
+            Query::Not(a) => !self.query_match(a),
+        }
+    }
+
+    fn ca_names(&self) -> impl Iterator<Item = String> + '_ {
+        self.aaguid
+            .ca
+            .iter()
+            .filter_map(|authority| authority.common_name())
     }
 }
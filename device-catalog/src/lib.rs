@@ -13,6 +13,7 @@ use std::fmt;
 
 use crate::device::*;
 use crate::query::Query;
+use crate::quirks::Quirks;
 use std::rc::Rc;
 
 use webauthn_attestation_ca::{AttestationCaList, AttestationCaListBuilder};
@@ -27,7 +28,7 @@ pub mod prelude {
 
     pub(crate) use openssl::error::ErrorStack as OpenSSLErrorStack;
     pub(crate) use openssl::x509;
-    pub(crate) use std::collections::BTreeSet;
+    pub(crate) use std::collections::{BTreeMap, BTreeSet};
     pub(crate) use std::rc::Rc;
 }
 
@@ -110,7 +111,7 @@ impl TryInto<AttestationCaList> for &Data {
                         authority.ca.clone(),
                         dev.aaguid.id,
                         sku.display_name.clone(),
-                        Default::default(),
+                        sku.display_names.clone(),
                     )?;
                 }
             }
@@ -120,9 +121,48 @@ impl TryInto<AttestationCaList> for &Data {
     }
 }
 
+// Allowed to match the AttestationCaList conversion above.
+#[allow(clippy::from_over_into)]
+impl Into<Quirks> for &Data {
+    fn into(self) -> Quirks {
+        // Sort by aaguid first so that the order devices were added in can never affect
+        // the output.
+        let mut devices: Vec<_> = self.devices.iter().collect();
+        devices.sort_by_key(|dev| dev.aaguid.id);
+
+        let mut quirks = Quirks::default();
+
+        for dev in devices {
+            if dev.quirks.is_empty() {
+                continue;
+            }
+
+            // A nil aaguid can never match a real device, so this is a data entry mistake.
+            if dev.aaguid.id.is_nil() {
+                tracing::warn!(
+                    mfr = %dev.mfr.display_name,
+                    "skipping quirks for device with nil aaguid"
+                );
+                continue;
+            }
+
+            quirks
+                .entry(dev.aaguid.id)
+                .or_default()
+                .extend(dev.quirks.iter().copied());
+        }
+
+        tracing::info!(count = quirks.len(), "emitted quirk entries");
+
+        quirks
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::prelude::*;
+    use crate::quirks::Quirk;
 
     #[test]
     fn test_init_data() {
@@ -133,4 +173,154 @@ mod test {
             println!("{0:?}", i);
         }
     }
+
+    #[test]
+    fn test_data_to_attestation_ca_list() {
+        // Both yubico devices share one root, so they collapse to a single CA that is
+        // restricted to both aaguids.
+        let data = DataBuilder::default().add_yubico().build();
+        let att_ca_list: AttestationCaList = (&data).try_into().unwrap();
+
+        assert_eq!(att_ca_list.len(), 1);
+        let att_ca = att_ca_list.cas().values().next().unwrap();
+        assert!(!att_ca.blanket_allow());
+
+        let aaguids: Vec<_> = att_ca.aaguids().keys().copied().collect();
+        assert_eq!(
+            aaguids,
+            vec![
+                uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b"),
+                uuid::uuid!("d8522d9f-575b-4866-88a9-ba99fa02f35b"),
+            ]
+        );
+        assert_eq!(
+            att_ca.aaguids()[&uuid::uuid!("d8522d9f-575b-4866-88a9-ba99fa02f35b")].description_en(),
+            "YubiKey Bio Series"
+        );
+    }
+
+    #[test]
+    fn test_data_query() {
+        let data = Data::all_known_devices();
+
+        let query = Query::parse("display_name contains bio").unwrap();
+        let bio = data.query(&query).unwrap();
+        let aaguids: Vec<_> = bio.iter().map(|dev| dev.aaguid.id).collect();
+        assert_eq!(
+            aaguids,
+            vec![uuid::uuid!("d8522d9f-575b-4866-88a9-ba99fa02f35b")]
+        );
+
+        let query = Query::parse(
+            "ca == 'Yubico U2F Root CA Serial 457200631' and not (display_name contains bio)",
+        )
+        .unwrap();
+        let fips = data.query(&query).unwrap();
+        let aaguids: Vec<_> = fips.iter().map(|dev| dev.aaguid.id).collect();
+        assert_eq!(
+            aaguids,
+            vec![uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b")]
+        );
+
+        let query = Query::parse("ca contains nitrokey").unwrap();
+        assert!(data.query(&query).is_none());
+    }
+
+    fn quirk_device(id: uuid::Uuid, quirks: &[Quirk]) -> Rc<Device> {
+        Rc::new(Device {
+            aaguid: Rc::new(Aaguid { id, ca: Vec::new() }),
+            images: Vec::default(),
+            quirks: quirks.iter().copied().collect(),
+            skus: Vec::default(),
+            mfr: Rc::new(Manufacturer {
+                display_name: "Test".to_string(),
+            }),
+        })
+    }
+
+    #[test]
+    fn test_quirks_deterministic() {
+        let devices = vec![
+            quirk_device(
+                uuid::uuid!("d8522d9f-575b-4866-88a9-ba99fa02f35b"),
+                &[Quirk::UserVerificationBroken, Quirk::RejectsEmptyPinAuth],
+            ),
+            quirk_device(
+                uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b"),
+                &[Quirk::RequiresResidentKeyForNonDiscoverable],
+            ),
+            quirk_device(uuid::uuid!("2fc0579f-8113-47ea-b116-bb5a8db9202a"), &[]),
+        ];
+
+        let forward = Data {
+            devices: devices.clone(),
+        };
+        let reverse = Data {
+            devices: devices.into_iter().rev().collect(),
+        };
+
+        let forward: Quirks = (&forward).into();
+        let reverse: Quirks = (&reverse).into();
+
+        // Devices without quirks are not emitted.
+        assert_eq!(forward.len(), 2);
+
+        assert_eq!(
+            serde_json::to_string(&forward).unwrap(),
+            serde_json::to_string(&reverse).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_quirks_nil_aaguid() {
+        let data = Data {
+            devices: vec![
+                quirk_device(uuid::Uuid::nil(), &[Quirk::RejectsEmptyPinAuth]),
+                quirk_device(
+                    uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b"),
+                    &[Quirk::UserVerificationBroken],
+                ),
+            ],
+        };
+
+        let quirks: Quirks = (&data).into();
+        assert_eq!(quirks.len(), 1);
+        assert!(!quirks.contains_key(&uuid::Uuid::nil()));
+    }
+
+    #[test]
+    fn test_localised_display_names() {
+        let yubico = Data::all_known_devices();
+        let ca = yubico.iter().next().unwrap().aaguid.ca.clone();
+        let id = uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b");
+
+        let sku = Rc::new(Sku {
+            display_name: "Test Key".to_string(),
+            display_names: BTreeMap::from([("de".to_string(), "Testschlüssel".to_string())]),
+            version: "1.0.0".to_string(),
+        });
+        assert_eq!(sku.display_name_for("de"), "Testschlüssel");
+        assert_eq!(sku.display_name_for("fr"), "Test Key");
+
+        let data = Data {
+            devices: vec![Rc::new(Device {
+                aaguid: Rc::new(Aaguid { id, ca }),
+                images: Vec::default(),
+                quirks: BTreeSet::default(),
+                skus: vec![sku],
+                mfr: Rc::new(Manufacturer {
+                    display_name: "Test".to_string(),
+                }),
+            })],
+        };
+
+        let att_ca_list: AttestationCaList = (&data).try_into().unwrap();
+        let att_ca = att_ca_list.cas().values().next().unwrap();
+        let desc = &att_ca.aaguids()[&id];
+        assert_eq!(desc.description_en(), "Test Key");
+        assert_eq!(
+            desc.description_localised().get("de").map(String::as_str),
+            Some("Testschlüssel")
+        );
+    }
 }
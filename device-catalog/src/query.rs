@@ -1,11 +1,199 @@
+//! A query language over the device catalog.
+//!
+//! `aaguid == abcd or (display_name contains "yubikey" and ca != "Other CA")`
+
+use std::str::FromStr;
 use uuid::Uuid;
 
+pub type QueryError = peg::error::ParseError<peg::str::LineCol>;
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Query {
     AaguidEqual(Uuid),
     AaguidNotEqual(Uuid),
+    DisplayNameEqual(String),
+    DisplayNameNotEqual(String),
+    DisplayNameContains(String),
+    CaEqual(String),
+    CaNotEqual(String),
+    CaContains(String),
 
     And(Box<Query>, Box<Query>),
     Or(Box<Query>, Box<Query>),
     Not(Box<Query>),
 }
+
+impl Query {
+    /// Parse a query expression. `and` binds more tightly than `or`, and parentheses may
+    /// be used to group terms.
+    pub fn parse(q: &str) -> Result<Self, QueryError> {
+        query::parse(q)
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(q: &str) -> Result<Self, Self::Err> {
+        Query::parse(q)
+    }
+}
+
+peg::parser! {
+    grammar query() for str {
+        pub rule parse() -> Query = precedence!{
+            a:(@) separator()+ "or" separator()+ b:@ {
+                Query::Or(
+                    Box::new(a),
+                    Box::new(b)
+                )
+            }
+            --
+            a:(@) separator()+ "and" separator()+ b:@ {
+                Query::And(
+                    Box::new(a),
+                    Box::new(b)
+                )
+            }
+            --
+            "not" separator()+ "(" e:parse() ")" {
+                Query::Not(Box::new(e))
+            }
+            --
+            "(" e:parse() ")" { e }
+            a:expr() { a }
+        }
+
+        rule separator() =
+            ['\n' | ' ' | '\t' ]
+
+        rule operator() =
+            ['\n' | ' ' | '\t' | '(' | ')' ]
+
+        pub(crate) rule expr() -> Query =
+            aaguid_eq_expr() /
+            aaguid_ne_expr() /
+            display_name_eq_expr() /
+            display_name_ne_expr() /
+            display_name_cnt_expr() /
+            ca_eq_expr() /
+            ca_ne_expr() /
+            ca_cnt_expr()
+
+        rule aaguid_eq_expr() -> Query =
+            "aaguid" separator()+ "==" separator()+ v:uuid() { Query::AaguidEqual(v) }
+
+        rule aaguid_ne_expr() -> Query =
+            "aaguid" separator()+ "!=" separator()+ v:uuid() { Query::AaguidNotEqual(v) }
+
+        rule display_name_eq_expr() -> Query =
+            "display_name" separator()+ "==" separator()+ v:octetstr() { Query::DisplayNameEqual(v) }
+
+        rule display_name_ne_expr() -> Query =
+            "display_name" separator()+ "!=" separator()+ v:octetstr() { Query::DisplayNameNotEqual(v) }
+
+        rule display_name_cnt_expr() -> Query =
+            "display_name" separator()+ "contains" separator()+ v:octetstr() { Query::DisplayNameContains(v) }
+
+        rule ca_eq_expr() -> Query =
+            "ca" separator()+ "==" separator()+ v:octetstr() { Query::CaEqual(v) }
+
+        rule ca_ne_expr() -> Query =
+            "ca" separator()+ "!=" separator()+ v:octetstr() { Query::CaNotEqual(v) }
+
+        rule ca_cnt_expr() -> Query =
+            "ca" separator()+ "contains" separator()+ v:octetstr() { Query::CaContains(v) }
+
+        pub(crate) rule uuid() -> Uuid =
+            s:$((!operator()[_])+) {? Uuid::from_str(s).map_err(|_| "invalid UUID" ) }
+
+        pub(crate) rule octetstr() -> String =
+            dquotedoctetstr() / squotedoctetstr() / bareoctetstr()
+
+        rule squotedoctetstr() -> String =
+            "\'" s:$((!"\'"[_])*) "\'" { s.to_string() }
+
+        rule dquotedoctetstr() -> String =
+            "\"" s:$((!"\""[_])*) "\"" { s.to_string() }
+
+        rule bareoctetstr() -> String =
+            s:$((!operator()[_])+) { s.to_string() }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const YK_5_FIPS: Uuid = uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b");
+    const YK_5_BIO: Uuid = uuid::uuid!("d8522d9f-575b-4866-88a9-ba99fa02f35b");
+
+    #[test]
+    fn test_query_attr() {
+        assert_eq!(
+            Query::parse("aaguid == 73bb0cd4-e502-49b8-9c6f-b59445bf720b"),
+            Ok(Query::AaguidEqual(YK_5_FIPS))
+        );
+        assert_eq!(
+            Query::parse("aaguid != 73bb0cd4-e502-49b8-9c6f-b59445bf720b"),
+            Ok(Query::AaguidNotEqual(YK_5_FIPS))
+        );
+        assert_eq!(
+            Query::parse("display_name contains \"YubiKey 5\""),
+            Ok(Query::DisplayNameContains("YubiKey 5".to_string()))
+        );
+        assert_eq!(
+            Query::parse("ca == 'Yubico U2F Root CA Serial 457200631'"),
+            Ok(Query::CaEqual(
+                "Yubico U2F Root CA Serial 457200631".to_string()
+            ))
+        );
+        assert!(Query::parse("aaguid == oueuntonaeunaun").is_err());
+        assert!(Query::parse("aaguid contains 73bb0cd4").is_err());
+        assert!(Query::parse("serial == 1").is_err());
+    }
+
+    #[test]
+    fn test_query_precedence() {
+        // and binds more tightly than or.
+        assert_eq!(
+            Query::parse(
+                "aaguid == 73bb0cd4-e502-49b8-9c6f-b59445bf720b or aaguid == d8522d9f-575b-4866-88a9-ba99fa02f35b and ca contains yubico"
+            ),
+            Ok(Query::Or(
+                Box::new(Query::AaguidEqual(YK_5_FIPS)),
+                Box::new(Query::And(
+                    Box::new(Query::AaguidEqual(YK_5_BIO)),
+                    Box::new(Query::CaContains("yubico".to_string())),
+                )),
+            ))
+        );
+
+        assert_eq!(
+            Query::parse(
+                "(aaguid == 73bb0cd4-e502-49b8-9c6f-b59445bf720b or aaguid == d8522d9f-575b-4866-88a9-ba99fa02f35b) and ca contains yubico"
+            ),
+            Ok(Query::And(
+                Box::new(Query::Or(
+                    Box::new(Query::AaguidEqual(YK_5_FIPS)),
+                    Box::new(Query::AaguidEqual(YK_5_BIO)),
+                )),
+                Box::new(Query::CaContains("yubico".to_string())),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_query_parens() {
+        assert_eq!(
+            Query::parse("not (display_name == 'YubiKey Bio Series')"),
+            Ok(Query::Not(Box::new(Query::DisplayNameEqual(
+                "YubiKey Bio Series".to_string()
+            ))))
+        );
+
+        assert!(Query::parse("(display_name == bio").is_err());
+        assert!(Query::parse("display_name == bio)").is_err());
+        assert!(Query::parse("((ca contains yubico)").is_err());
+    }
+}
@@ -1,11 +1,212 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
 use uuid::Uuid;
 
+/// A known misbehaviour of a device that clients may need to work around. The textual form
+/// (via `Display` and `FromStr`) is the same `snake_case` name used by serde.
 #[derive(Deserialize, Serialize, Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
 pub enum Quirk {
+    /// Serialised as `QuirkMcQuirkleton` before quirk names were `snake_case`, which is still
+    /// accepted when reading.
+    #[serde(alias = "QuirkMcQuirkleton")]
     QuirkMcQuirkleton,
+    /// The device rejects a zero length pinUvAuthParam rather than prompting for touch.
+    RejectsEmptyPinAuth,
+    /// The device advertises user verification, but does not perform it correctly.
+    UserVerificationBroken,
+    /// The device fails to create non-discoverable credentials unless resident key is requested.
+    RequiresResidentKeyForNonDiscoverable,
+}
+
+impl Quirk {
+    /// Every known quirk.
+    pub const ALL: &'static [Quirk] = &[
+        Quirk::QuirkMcQuirkleton,
+        Quirk::RejectsEmptyPinAuth,
+        Quirk::UserVerificationBroken,
+        Quirk::RequiresResidentKeyForNonDiscoverable,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Quirk::QuirkMcQuirkleton => "quirk_mc_quirkleton",
+            Quirk::RejectsEmptyPinAuth => "rejects_empty_pin_auth",
+            Quirk::UserVerificationBroken => "user_verification_broken",
+            Quirk::RequiresResidentKeyForNonDiscoverable => {
+                "requires_resident_key_for_non_discoverable"
+            }
+        }
+    }
+}
+
+impl FromStr for Quirk {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "QuirkMcQuirkleton" {
+            return Ok(Quirk::QuirkMcQuirkleton);
+        }
+
+        Quirk::ALL
+            .iter()
+            .find(|q| q.as_str() == s)
+            .copied()
+            .ok_or(())
+    }
+}
+
+impl fmt::Display for Quirk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
 }
 
 pub type Quirks = BTreeMap<Uuid, BTreeSet<Quirk>>;
+
+/// The current version of the [QuirksFile] format.
+pub const QUIRKS_FILE_VERSION: u32 = 1;
+
+/// The serialised form of [Quirks], as consumed by other crates. Unknown quirk names are
+/// skipped when deserialising so that older consumers can read files from newer producers.
+/// A file with a version newer than [QUIRKS_FILE_VERSION] is rejected, as its format may
+/// have changed in ways this consumer can not detect.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct QuirksFile {
+    #[serde(deserialize_with = "deserialize_version")]
+    pub version: u32,
+    #[serde(deserialize_with = "deserialize_quirks_lenient")]
+    pub quirks: Quirks,
+}
+
+impl From<Quirks> for QuirksFile {
+    fn from(quirks: Quirks) -> Self {
+        QuirksFile {
+            version: QUIRKS_FILE_VERSION,
+            quirks,
+        }
+    }
+}
+
+fn deserialize_version<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let version = u32::deserialize(deserializer)?;
+    if version > QUIRKS_FILE_VERSION {
+        return Err(serde::de::Error::custom(format!(
+            "unsupported quirks file version {version}, the newest supported is {QUIRKS_FILE_VERSION}"
+        )));
+    }
+    Ok(version)
+}
+
+fn deserialize_quirks_lenient<'de, D>(deserializer: D) -> Result<Quirks, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw: BTreeMap<Uuid, Vec<String>> = BTreeMap::deserialize(deserializer)?;
+
+    Ok(raw
+        .into_iter()
+        .map(|(aaguid, names)| {
+            let quirks = names
+                .iter()
+                .filter_map(|name| {
+                    Quirk::from_str(name)
+                        .map_err(|_| tracing::debug!(%aaguid, %name, "skipping unknown quirk"))
+                        .ok()
+                })
+                .collect();
+            (aaguid, quirks)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_quirk_str_round_trip() {
+        for q in Quirk::ALL.iter().copied() {
+            assert_eq!(Quirk::from_str(&q.to_string()), Ok(q));
+            assert_eq!(serde_json::to_string(&q).unwrap(), format!("\"{}\"", q));
+        }
+
+        assert_eq!(
+            Quirk::from_str("rejects_empty_pin_auth"),
+            Ok(Quirk::RejectsEmptyPinAuth)
+        );
+        assert_eq!(Quirk::from_str("RejectsEmptyPinAuth"), Err(()));
+    }
+
+    #[test]
+    fn test_quirk_legacy_name() {
+        // The name used before quirks were snake_case is still read, but no longer written.
+        assert_eq!(
+            serde_json::from_str::<Quirk>("\"QuirkMcQuirkleton\"").unwrap(),
+            Quirk::QuirkMcQuirkleton
+        );
+        assert_eq!(
+            Quirk::from_str("QuirkMcQuirkleton"),
+            Ok(Quirk::QuirkMcQuirkleton)
+        );
+        assert_eq!(Quirk::QuirkMcQuirkleton.to_string(), "quirk_mc_quirkleton");
+
+        let aaguid = uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b");
+        let data = r#"{
+            "version": 1,
+            "quirks": {
+                "73bb0cd4-e502-49b8-9c6f-b59445bf720b": ["QuirkMcQuirkleton"]
+            }
+        }"#;
+        let file: QuirksFile = serde_json::from_str(data).unwrap();
+        assert_eq!(
+            file.quirks.get(&aaguid),
+            Some(&BTreeSet::from([Quirk::QuirkMcQuirkleton]))
+        );
+    }
+
+    #[test]
+    fn test_quirks_file_unknown_quirk() {
+        let aaguid = uuid::uuid!("73bb0cd4-e502-49b8-9c6f-b59445bf720b");
+        let data = r#"{
+            "version": 1,
+            "quirks": {
+                "73bb0cd4-e502-49b8-9c6f-b59445bf720b": [
+                    "rejects_empty_pin_auth",
+                    "quirk_from_the_future"
+                ]
+            }
+        }"#;
+
+        let file: QuirksFile = serde_json::from_str(data).unwrap();
+        assert_eq!(file.version, QUIRKS_FILE_VERSION);
+        assert_eq!(
+            file.quirks.get(&aaguid),
+            Some(&BTreeSet::from([Quirk::RejectsEmptyPinAuth]))
+        );
+
+        // And it round trips.
+        let json = serde_json::to_string(&file).unwrap();
+        assert_eq!(serde_json::from_str::<QuirksFile>(&json).unwrap(), file);
+    }
+
+    #[test]
+    fn test_quirks_file_version() {
+        let future = QUIRKS_FILE_VERSION + 1;
+        let data = format!(r#"{{ "version": {future}, "quirks": {{}} }}"#);
+        let err = serde_json::from_str::<QuirksFile>(&data).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&format!("unsupported quirks file version {future}")));
+
+        let data = format!(r#"{{ "version": {QUIRKS_FILE_VERSION}, "quirks": {{}} }}"#);
+        let file: QuirksFile = serde_json::from_str(&data).unwrap();
+        assert_eq!(file, QuirksFile::from(Quirks::new()));
+    }
+}
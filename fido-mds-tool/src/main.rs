@@ -12,6 +12,7 @@
 
 use clap::Parser;
 use clap::{Args, Subcommand};
+use std::collections::BTreeMap;
 use std::fs;
 use std::fs::File;
 use std::io::Write;
@@ -91,6 +92,14 @@ pub enum Opt {
     },
     /// Query and display metadata for FIDO2 devices based on a query expression.
     Query(QueryOpt),
+    /// Display summary statistics about the devices in an MDS file.
+    Stats {
+        #[clap(flatten)]
+        common: CommonOpt,
+        /// Output the statistics as json.
+        #[clap(long)]
+        json: bool,
+    },
 }
 
 impl Opt {
@@ -101,6 +110,10 @@ impl Opt {
             | Opt::ListFido2 {
                 common: CommonOpt { debug, .. },
                 ..
+            }
+            | Opt::Stats {
+                common: CommonOpt { debug, .. },
+                ..
             } => *debug,
             Opt::Query(QueryOpt {
                 common: CommonOpt { debug, .. },
@@ -288,6 +301,72 @@ fn main() {
                 }
             }
         }
+        Opt::Stats {
+            common: CommonOpt { debug: _, path },
+            json,
+        } => {
+            trace!("{:?}", path);
+
+            let s = match fs::read_to_string(path) {
+                Ok(s) => s,
+                Err(e) => {
+                    tracing::error!(?e);
+                    return;
+                }
+            };
+
+            match FidoMds::from_str(&s) {
+                Ok(mds) => display_stats(&mds, json),
+                Err(e) => {
+                    tracing::error!(?e);
+                }
+            }
+        }
+    }
+}
+
+fn display_stats(mds: &FidoMds, json: bool) {
+    let fido2_with_roots = mds
+        .fido2
+        .iter()
+        .filter(|fd| !fd.attestation_root_certificates.is_empty())
+        .count();
+    let u2f_with_roots = mds
+        .u2f
+        .iter()
+        .filter(|fd| !fd.attestation_root_certificates.is_empty())
+        .count();
+    let fido2_revoked = mds.fido2.iter().filter(|fd| fd.is_revoked()).count();
+
+    let mut certification_levels: BTreeMap<&str, usize> = BTreeMap::new();
+    for fd in mds.fido2.iter() {
+        let level = fd
+            .latest_certification()
+            .map(|sr| sr.as_str())
+            .unwrap_or("No Certification");
+        *certification_levels.entry(level).or_default() += 1;
+    }
+
+    if json {
+        let stats = serde_json::json!({
+            "fido2": mds.fido2.len(),
+            "fido2_with_attestation_roots": fido2_with_roots,
+            "fido2_revoked": fido2_revoked,
+            "u2f": mds.u2f.len(),
+            "u2f_with_attestation_roots": u2f_with_roots,
+            "fido2_certification_levels": certification_levels,
+        });
+        println!("{}", stats);
+    } else {
+        println!("fido2: {}", mds.fido2.len());
+        println!("  with attestation roots: {}", fido2_with_roots);
+        println!("  revoked: {}", fido2_revoked);
+        println!("  certification levels:");
+        for (level, count) in certification_levels.iter() {
+            println!("    - {}: {}", level, count);
+        }
+        println!("u2f: {}", mds.u2f.len());
+        println!("  with attestation roots: {}", u2f_with_roots);
     }
 }
 
@@ -36,15 +36,17 @@ use crate::mds::VerificationMethodAndCombinations;
 use crate::mds::{
     AttestationType, AuthenticationAlgorithm, AuthenticatorGetInfo, BiometricAccuracyDescriptor,
     CodeAccuracyDescriptor, EcdaaAnchor, ExtensionDescriptor, KeyProtection,
-    PatternAccuracyDescriptor, ProtocolFamily, PublicKeyAlg,
+    PatternAccuracyDescriptor, ProtocolFamily, PublicKeyAlg, Upv,
 };
 
+use crate::patch::PatchPolicy;
 use crate::query::{AttrValueAssertion, Query};
 
 use webauthn_attestation_ca::{AttestationCaList, AttestationCaListBuilder};
 
 use base64::{engine::general_purpose::STANDARD, Engine};
 use compact_jwt::JwtError;
+use openssl::x509;
 use std::cmp::Ordering;
 use std::fmt;
 use std::rc;
@@ -57,6 +59,119 @@ use uuid::Uuid;
 
 pub const FIDO_MDS_URL: &str = "https://mds.fidoalliance.org/";
 
+/// An error from parsing metadata that is not part of a signed metadata blob, or from
+/// interpreting the dates of a parsed blob.
+#[derive(Debug)]
+pub enum MdsError {
+    /// The json is invalid, or does not match the structure of a metadata statement.
+    Json(serde_json::Error),
+    /// The metadata statement is inconsistent or invalid, and could not be converted.
+    InvalidStatement,
+    /// The metadata statement is valid, but does not describe a FIDO2 device.
+    NotFido2,
+    /// The date is not an ISO-8601 calendar date (`YYYY-MM-DD`).
+    InvalidDate(String),
+}
+
+impl fmt::Display for MdsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MdsError::Json(e) => write!(f, "invalid metadata statement json: {e}"),
+            MdsError::InvalidStatement => write!(f, "invalid metadata statement"),
+            MdsError::NotFido2 => write!(f, "metadata statement does not describe a FIDO2 device"),
+            MdsError::InvalidDate(date) => write!(f, "invalid date {date:?}, expected YYYY-MM-DD"),
+        }
+    }
+}
+
+impl std::error::Error for MdsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MdsError::Json(e) => Some(e),
+            MdsError::InvalidStatement | MdsError::NotFido2 | MdsError::InvalidDate(_) => None,
+        }
+    }
+}
+
+/// A calendar date, as used by the metadata service in the ISO-8601 form `YYYY-MM-DD`. Dates
+/// order chronologically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MdsDate {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl MdsDate {
+    /// Create a date, if the day exists in that month and year.
+    pub fn new(year: u16, month: u8, day: u8) -> Option<Self> {
+        let leap_year = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+        let days_in_month = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap_year => 29,
+            2 => 28,
+            _ => return None,
+        };
+
+        if (1..=days_in_month).contains(&day) {
+            Some(MdsDate { year, month, day })
+        } else {
+            None
+        }
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+}
+
+impl FromStr for MdsDate {
+    type Err = MdsError;
+
+    fn from_str(date: &str) -> Result<Self, Self::Err> {
+        let invalid = || MdsError::InvalidDate(date.to_string());
+
+        let mut parts = date.split('-');
+        let (Some(year), Some(month), Some(day), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+
+        if year.len() != 4
+            || month.len() != 2
+            || day.len() != 2
+            || !date.chars().all(|c| c == '-' || c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let year = year.parse().map_err(|_| invalid())?;
+        let month = month.parse().map_err(|_| invalid())?;
+        let day = day.parse().map_err(|_| invalid())?;
+
+        MdsDate::new(year, month, day).ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for MdsDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+// Returned for devices that do not provide authenticatorGetInfo.
+static EMPTY_OPTIONS: BTreeMap<String, bool> = BTreeMap::new();
+
 /// A status report for an authenticator. This describes the specific state of this device and
 /// it's FIDO certification status. The effective date acts as a publishing time, where if the
 /// effective date is `None` it is considered 'the latest report'.
@@ -588,6 +703,28 @@ impl StatusReport {
         }
     }
 
+    // Orders reports that share an effective date. Certifications sort before security notices,
+    // and revocations sort last, so that the most severe report is treated as the latest.
+    fn precedence(&self) -> u8 {
+        match self {
+            StatusReport::NotFidoCertified { .. } => 0,
+            StatusReport::SelfAssertionSubmitted { .. } => 1,
+            StatusReport::FidoCertified { .. } => 10,
+            StatusReport::FidoCertifiedL1 { .. } => 11,
+            StatusReport::FidoCertifiedL1Plus { .. } => 12,
+            StatusReport::FidoCertifiedL2 { .. } => 20,
+            StatusReport::FidoCertifiedL2Plus { .. } => 21,
+            StatusReport::FidoCertifiedL3 { .. } => 30,
+            StatusReport::FidoCertifiedL3Plus { .. } => 31,
+            StatusReport::UpdateAvailable { .. } => 40,
+            StatusReport::UserVerificationBypass { .. } => 41,
+            StatusReport::UserKeyRemoteCompromise { .. } => 42,
+            StatusReport::UserKeyPhysicalCompromise { .. } => 43,
+            StatusReport::AttestationKeyCompromise { .. } => 44,
+            StatusReport::Revoked { .. } => 45,
+        }
+    }
+
     pub(crate) fn numeric(&self) -> u8 {
         match self {
             StatusReport::NotFidoCertified { .. }
@@ -610,6 +747,31 @@ impl StatusReport {
     fn gte(&self, level: &AuthenticatorStatus) -> bool {
         self.numeric() >= level.numeric()
     }
+
+    /// If this report revokes trust in the device.
+    pub fn is_revoked(&self) -> bool {
+        matches!(
+            self,
+            StatusReport::Revoked { .. } | StatusReport::AttestationKeyCompromise { .. }
+        )
+    }
+
+    /// If this report describes the certification of the device, rather than a security or
+    /// update notice.
+    fn is_certification(&self) -> bool {
+        matches!(
+            self,
+            StatusReport::NotFidoCertified { .. }
+                | StatusReport::SelfAssertionSubmitted { .. }
+                | StatusReport::FidoCertified { .. }
+                | StatusReport::FidoCertifiedL1 { .. }
+                | StatusReport::FidoCertifiedL1Plus { .. }
+                | StatusReport::FidoCertifiedL2 { .. }
+                | StatusReport::FidoCertifiedL2Plus { .. }
+                | StatusReport::FidoCertifiedL3 { .. }
+                | StatusReport::FidoCertifiedL3Plus { .. }
+        )
+    }
 }
 
 impl PartialOrd for StatusReport {
@@ -626,6 +788,8 @@ impl Ord for StatusReport {
             (None, Some(_)) => Ordering::Greater,
             (Some(a), Some(b)) => a.cmp(b),
         }
+        // Without this, a second report on the same date would be discarded from the set.
+        .then_with(|| self.precedence().cmp(&other.precedence()))
     }
 }
 
@@ -727,6 +891,47 @@ impl fmt::Display for UserVerificationMethod {
     }
 }
 
+/// The flat set of user verification methods a device mentions in any combination. This
+/// discards the grouping of `user_verification_details`, so it can answer "does this device
+/// have a fingerprint reader" but not "which methods are required together".
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UvmCapabilities {
+    pub presence: bool,
+    /// A passcode entered either on the device, or on the client.
+    pub passcode: bool,
+    pub fingerprint: bool,
+    pub handprint: bool,
+    pub eyeprint: bool,
+    pub voiceprint: bool,
+    pub faceprint: bool,
+    pub location: bool,
+    pub pattern: bool,
+}
+
+impl UvmCapabilities {
+    fn from_uvm(user_verification_details: &[Vec<UserVerificationMethod>]) -> Self {
+        user_verification_details
+            .iter()
+            .flat_map(|uvm_and| uvm_and.iter())
+            .fold(UvmCapabilities::default(), |mut caps, uvm| {
+                match uvm {
+                    UserVerificationMethod::None => {}
+                    UserVerificationMethod::PresenceInternal => caps.presence = true,
+                    UserVerificationMethod::PasscodeInternal(_)
+                    | UserVerificationMethod::PasscodeExternal(_) => caps.passcode = true,
+                    UserVerificationMethod::FingerprintInternal(_) => caps.fingerprint = true,
+                    UserVerificationMethod::HandprintInternal(_) => caps.handprint = true,
+                    UserVerificationMethod::EyeprintInternal(_) => caps.eyeprint = true,
+                    UserVerificationMethod::VoiceprintInternal(_) => caps.voiceprint = true,
+                    UserVerificationMethod::FaceprintInternal(_) => caps.faceprint = true,
+                    UserVerificationMethod::LocationInternal => caps.location = true,
+                    UserVerificationMethod::PatternInternal(_) => caps.pattern = true,
+                }
+                caps
+            })
+    }
+}
+
 impl TryFrom<VerificationMethodAndCombinations> for UserVerificationMethod {
     type Error = ();
 
@@ -797,6 +1002,8 @@ pub struct UAF {
     pub alternative_descriptions: BTreeMap<String, String>,
     /// The latest firmware version of the device.
     pub authenticator_version: u32,
+    /// The versions of the authenticator protocol this device supports.
+    pub upv: Vec<Upv>,
     /// The supported cryptographic algorithms this device supports.
     pub authentication_algorithms: Vec<AuthenticationAlgorithm>,
     /// The encoding of the devices public key when registered
@@ -863,6 +1070,8 @@ pub struct U2F {
     pub alternative_descriptions: BTreeMap<String, String>,
     /// The latest firmware version of the device.
     pub authenticator_version: u32,
+    /// The versions of the authenticator protocol this device supports.
+    pub upv: Vec<Upv>,
     /// The supported cryptographic algorithms this device supports.
     pub authentication_algorithms: Vec<AuthenticationAlgorithm>,
     /// The encoding of the devices public key when registered
@@ -924,6 +1133,31 @@ impl fmt::Display for U2F {
     }
 }
 
+impl U2F {
+    /// The flat set of user verification methods this device supports in any combination.
+    pub fn uvm_capabilities(&self) -> UvmCapabilities {
+        UvmCapabilities::from_uvm(&self.user_verification_details)
+    }
+
+    /// The transports declared by authenticatorGetInfo. Empty if the device does not provide
+    /// authenticatorGetInfo, which is the case for most U2F devices.
+    pub fn transports(&self) -> &[AuthenticatorTransport] {
+        self.authenticator_get_info
+            .as_ref()
+            .map(|agi| agi.transports.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The option flags declared by authenticatorGetInfo. Empty if the device does not provide
+    /// authenticatorGetInfo.
+    pub fn options(&self) -> &BTreeMap<String, bool> {
+        self.authenticator_get_info
+            .as_ref()
+            .map(|agi| &agi.options)
+            .unwrap_or(&EMPTY_OPTIONS)
+    }
+}
+
 /// A metadata statement describing a FIDO2 device.
 #[derive(Debug, Clone)]
 pub struct FIDO2 {
@@ -935,6 +1169,8 @@ pub struct FIDO2 {
     pub alternative_descriptions: BTreeMap<String, String>,
     /// The latest firmware version of the device.
     pub authenticator_version: u32,
+    /// The versions of the authenticator protocol this device supports.
+    pub upv: Vec<Upv>,
     /// The supported cryptographic algorithms this device supports.
     pub authentication_algorithms: Vec<AuthenticationAlgorithm>,
     /// The encoding of the devices public key when registered
@@ -1020,6 +1256,26 @@ impl FIDO2 {
                 .unwrap_or(false),
             AttrValueAssertion::StatusGte(s) => self.status_reports.iter().any(|sr| sr.gte(s)),
             AttrValueAssertion::StatusLt(s) => self.status_reports.iter().any(|sr| !sr.gte(s)),
+            AttrValueAssertion::LevelEq(l) => self
+                .certified_level()
+                .map(|n| n == l.numeric())
+                .unwrap_or(false),
+            AttrValueAssertion::LevelGt(l) => self
+                .certified_level()
+                .map(|n| n > l.numeric())
+                .unwrap_or(false),
+            AttrValueAssertion::LevelGte(l) => self
+                .certified_level()
+                .map(|n| n >= l.numeric())
+                .unwrap_or(false),
+            AttrValueAssertion::LevelLt(l) => self
+                .certified_level()
+                .map(|n| n < l.numeric())
+                .unwrap_or(false),
+            AttrValueAssertion::LevelLte(l) => self
+                .certified_level()
+                .map(|n| n <= l.numeric())
+                .unwrap_or(false),
             AttrValueAssertion::TransportEq(t) => self
                 .authenticator_get_info
                 .as_ref()
@@ -1033,6 +1289,17 @@ impl FIDO2 {
         }
     }
 
+    /// The numeric level of the latest certification, if the device holds one. Devices that
+    /// are uncertified, only self asserted, or revoked have no level.
+    fn certified_level(&self) -> Option<u8> {
+        if self.is_revoked() {
+            return None;
+        }
+        self.latest_certification()
+            .map(|sr| sr.numeric())
+            .filter(|n| *n > 0)
+    }
+
     fn query_match(&self, q: &Query) -> bool {
         match q {
             Query::Op(ava) => self.query_attr(ava),
@@ -1041,12 +1308,102 @@ impl FIDO2 {
             Query::Not(a) => !self.query_match(a),
         }
     }
+
+    /// The status reports for this device, ordered by effective date so that the last report
+    /// is the current status. Reports without a date are considered the most recent.
+    pub fn status_reports(&self) -> &BTreeSet<StatusReport> {
+        &self.status_reports
+    }
+
+    /// The most recent status report for this device.
+    pub fn latest_status_report(&self) -> Option<&StatusReport> {
+        self.status_reports.last()
+    }
+
+    /// The most recent certification status report for this device. Security and update notices
+    /// are skipped so that they do not mask the certification level of the device.
+    pub fn latest_certification(&self) -> Option<&StatusReport> {
+        self.status_reports
+            .iter()
+            .rev()
+            .find(|sr| sr.is_certification())
+    }
+
+    /// If this device has been revoked or had its attestation key compromised. A revocation is
+    /// only superseded by a later certification report. Security and update notices do not
+    /// lift it.
+    pub fn is_revoked(&self) -> bool {
+        self.status_reports
+            .iter()
+            .rev()
+            .find(|sr| sr.is_revoked() || sr.is_certification())
+            .map(|sr| sr.is_revoked())
+            .unwrap_or(false)
+    }
+
+    /// The flat set of user verification methods this device supports in any combination.
+    pub fn uvm_capabilities(&self) -> UvmCapabilities {
+        UvmCapabilities::from_uvm(&self.user_verification_details)
+    }
+
+    /// The vendor specific firmware version reported by authenticatorGetInfo, if any. This is
+    /// distinct from `authenticator_version`, which is the version the metadata statement
+    /// applies to.
+    pub fn firmware_version(&self) -> Option<u32> {
+        self.authenticator_get_info
+            .as_ref()
+            .and_then(|agi| agi.firmware_version())
+    }
+
+    /// If this device has any user verification method beyond presence, such as a passcode or
+    /// biometric.
+    pub fn supports_uv(&self) -> bool {
+        self.user_verification_details
+            .iter()
+            .flat_map(|and| and.iter())
+            .any(|uvm| {
+                !matches!(
+                    uvm,
+                    UserVerificationMethod::None | UserVerificationMethod::PresenceInternal
+                )
+            })
+    }
+
+    /// If this device is able to create resident keys (discoverable credentials) as declared
+    /// by the `rk` option of authenticatorGetInfo.
+    pub fn supports_rk(&self) -> bool {
+        self.options().get("rk").copied().unwrap_or(false)
+    }
+
+    /// The transports declared by authenticatorGetInfo. Empty if the device does not provide
+    /// authenticatorGetInfo.
+    pub fn transports(&self) -> &[AuthenticatorTransport] {
+        self.authenticator_get_info
+            .as_ref()
+            .map(|agi| agi.transports.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The option flags declared by authenticatorGetInfo. Empty if the device does not provide
+    /// authenticatorGetInfo.
+    pub fn options(&self) -> &BTreeMap<String, bool> {
+        self.authenticator_get_info
+            .as_ref()
+            .map(|agi| &agi.options)
+            .unwrap_or(&EMPTY_OPTIONS)
+    }
 }
 
 impl TryFrom<RawFidoDevice> for FidoDevice {
     type Error = ();
 
     fn try_from(rawdevice: RawFidoDevice) -> Result<Self, Self::Error> {
+        FidoDevice::from_raw(rawdevice, PatchPolicy::WarnAndPassthrough)
+    }
+}
+
+impl FidoDevice {
+    fn from_raw(rawdevice: RawFidoDevice, policy: PatchPolicy) -> Result<Self, ()> {
         let RawFidoDevice {
             aaid,
             aaguid,
@@ -1097,7 +1454,7 @@ impl TryFrom<RawFidoDevice> for FidoDevice {
         // types / values that we want to expose.
         let RawMetadataStatement {
             legal_header: _,
-            upv: _,
+            upv,
             aaid: _,
             aaguid: _,
             attestation_certificate_key_identifiers: _,
@@ -1208,7 +1565,7 @@ impl TryFrom<RawFidoDevice> for FidoDevice {
             })
             .collect();
 
-        match patch::user_verification_method(aaguid, &user_verification_details) {
+        match patch::user_verification_method(policy, aaguid, &user_verification_details) {
             Ok(None) => {
                 // No patching needed.
             }
@@ -1217,9 +1574,18 @@ impl TryFrom<RawFidoDevice> for FidoDevice {
                 inconsistent_data = true;
                 std::mem::swap(&mut uvm_patch, &mut user_verification_details)
             }
-            Err(_e) => {
-                error!("Unable to patch user verification methods. This is a bug and should be reported. https://github.com/kanidm/webauthn-rs/issues");
-            }
+            Err(()) => match policy {
+                PatchPolicy::Strict => {
+                    error!(
+                        "Unable to patch user verification methods, rejecting device: {:?}, {:?}, {:?}",
+                        aaid, aaguid, attestation_certificate_key_identifiers
+                    );
+                    return Err(());
+                }
+                PatchPolicy::WarnAndPassthrough => {
+                    error!("Unable to patch user verification methods. This is a bug and should be reported. https://github.com/kanidm/webauthn-rs/issues");
+                }
+            },
         }
 
         for uvm_and in user_verification_details.iter() {
@@ -1304,6 +1670,7 @@ impl TryFrom<RawFidoDevice> for FidoDevice {
                 description,
                 alternative_descriptions,
                 authenticator_version,
+                upv,
                 authentication_algorithms,
                 public_key_alg_and_encodings,
                 attestation_types,
@@ -1323,6 +1690,7 @@ impl TryFrom<RawFidoDevice> for FidoDevice {
                 description,
                 alternative_descriptions,
                 authenticator_version,
+                upv,
                 authentication_algorithms,
                 public_key_alg_and_encodings,
                 attestation_types,
@@ -1344,6 +1712,7 @@ impl TryFrom<RawFidoDevice> for FidoDevice {
                 description,
                 alternative_descriptions,
                 authenticator_version,
+                upv,
                 authentication_algorithms,
                 public_key_alg_and_encodings,
                 attestation_types,
@@ -1379,10 +1748,20 @@ pub struct FidoMds {
     pub uaf: Vec<UAF>,
     /// The set of (legacy) U2f device metadata that exists within the Metadata Statement.
     pub u2f: Vec<rc::Rc<U2F>>,
+    next_update: String,
 }
 
 impl From<RawFidoMds> for FidoMds {
     fn from(rawmds: RawFidoMds) -> Self {
+        FidoMds::from_raw(rawmds, PatchPolicy::WarnAndPassthrough)
+    }
+}
+
+impl FidoMds {
+    /// Convert the raw metadata, applying `policy` to devices whose uvm patch no longer
+    /// matches the MDS. Devices that fail to convert are excluded.
+    fn from_raw(rawmds: RawFidoMds, policy: PatchPolicy) -> Self {
+        let next_update = rawmds.next_update;
         let mut fido2 = Vec::new();
         let mut uaf = Vec::new();
         let mut u2f = Vec::new();
@@ -1390,7 +1769,7 @@ impl From<RawFidoMds> for FidoMds {
         rawmds
             .entries
             .into_iter()
-            .filter_map(|device| device.try_into().ok())
+            .filter_map(|device| FidoDevice::from_raw(device, policy).ok())
             .for_each(|fd| match fd {
                 FidoDevice::Uaf(dev) => uaf.push(dev),
                 FidoDevice::U2F(dev) => {
@@ -1413,19 +1792,84 @@ impl From<RawFidoMds> for FidoMds {
         u2f.sort_unstable_by(|a, b| a.description.cmp(&b.description));
         uaf.sort_unstable_by(|a, b| a.description.cmp(&b.description));
 
-        FidoMds { fido2, uaf, u2f }
+        FidoMds {
+            fido2,
+            uaf,
+            u2f,
+            next_update,
+        }
     }
 }
 
 impl FromStr for FidoMds {
     type Err = JwtError;
 
+    /// Parse the metadata jwt. The x5c chain of the jwt is verified to the FIDO Alliance's
+    /// root certificate authority (GlobalSign Root CA - R3) before the payload is trusted.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         RawFidoMds::from_str(s).map(|rawmds| rawmds.into())
     }
 }
 
 impl FidoMds {
+    /// Parse the metadata jwt, verifying the x5c chain of the jwt to `root_ca` rather than to
+    /// the FIDO Alliance's root certificate authority. The signature over the payload is checked
+    /// before it is parsed. This is useful if you are consuming metadata from a mirror or
+    /// alternate metadata service that signs with a different authority.
+    pub fn from_str_verified(s: &str, root_ca: &x509::X509) -> Result<Self, JwtError> {
+        RawFidoMds::from_str_verified(s, root_ca).map(|rawmds| rawmds.into())
+    }
+
+    /// Parse the metadata jwt as [FidoMds::from_str], applying `policy` to devices with a
+    /// known uvm patch whose uvm in the MDS no longer matches the patch. With
+    /// [PatchPolicy::Strict] these devices are excluded. [FidoMds::from_str] and
+    /// [FidoMds::from_str_verified] use [PatchPolicy::WarnAndPassthrough], and keep these
+    /// devices with their uvm unpatched.
+    pub fn from_str_with_policy(s: &str, policy: PatchPolicy) -> Result<Self, JwtError> {
+        RawFidoMds::from_str(s).map(|rawmds| FidoMds::from_raw(rawmds, policy))
+    }
+
+    /// Parse the metadata jwt as [FidoMds::from_str_verified], applying `policy` as
+    /// [FidoMds::from_str_with_policy] does.
+    pub fn from_str_verified_with_policy(
+        s: &str,
+        root_ca: &x509::X509,
+        policy: PatchPolicy,
+    ) -> Result<Self, JwtError> {
+        RawFidoMds::from_str_verified(s, root_ca).map(|rawmds| FidoMds::from_raw(rawmds, policy))
+    }
+
+    /// Parse a single standalone metadata statement, such as those vendors publish for devices
+    /// that are not yet in the FIDO Alliance's metadata service. Unlike [FidoMds::from_str] the
+    /// statement is not signed, so the caller is responsible for trusting its source.
+    ///
+    /// A standalone statement carries no status reports, so the returned device is treated as
+    /// uncertified by status based filters.
+    pub fn from_metadata_statement(s: &str) -> Result<FIDO2, MdsError> {
+        let metadata_statement: RawMetadataStatement =
+            serde_json::from_str(s).map_err(MdsError::Json)?;
+
+        let rawdevice = RawFidoDevice {
+            aaid: metadata_statement.aaid.clone(),
+            aaguid: metadata_statement.aaguid,
+            attestation_certificate_key_identifiers: metadata_statement
+                .attestation_certificate_key_identifiers
+                .clone(),
+            metadata_statement,
+            biometric_status_reports: Vec::new(),
+            status_reports: Vec::new(),
+            time_of_last_status_change: String::new(),
+            rogue_list_url: None,
+            rogue_list_hash: None,
+        };
+
+        match FidoDevice::try_from(rawdevice) {
+            Ok(FidoDevice::FIDO2(fd)) => Ok(fd),
+            Ok(_) => Err(MdsError::NotFido2),
+            Err(()) => Err(MdsError::InvalidStatement),
+        }
+    }
+
     pub fn fido2_query(&self, query: &Query) -> Option<Vec<rc::Rc<FIDO2>>> {
         debug!(?query);
 
@@ -1446,6 +1890,65 @@ impl FidoMds {
         }
     }
 
+    /// Return the set of FIDO2 devices that match the predicate.
+    pub fn filter_fido2(&self, pred: impl Fn(&FIDO2) -> bool) -> Vec<&FIDO2> {
+        self.fido2
+            .iter()
+            .map(|fd| fd.as_ref())
+            .filter(|fd| pred(fd))
+            .collect()
+    }
+
+    /// Find the FIDO2 device with this aaguid.
+    pub fn with_aaguid(&self, aaguid: Uuid) -> Option<&FIDO2> {
+        // fido2 is sorted by aaguid during parsing.
+        self.fido2
+            .binary_search_by(|fd| fd.aaguid.cmp(&aaguid))
+            .ok()
+            .and_then(|idx| self.fido2.get(idx))
+            .map(|fd| fd.as_ref())
+    }
+
+    /// The date by which the next update of the metadata will be published. Errors if the
+    /// metadata does not carry a valid date.
+    pub fn next_update(&self) -> Result<MdsDate, MdsError> {
+        MdsDate::from_str(&self.next_update)
+    }
+
+    /// If the next update of this metadata was due before `today`, meaning a newer copy
+    /// should be fetched. Errors if the metadata does not carry a valid date.
+    pub fn is_stale(&self, today: MdsDate) -> Result<bool, MdsError> {
+        Ok(self.next_update()? < today)
+    }
+
+    /// Return the set of FIDO2 devices whose latest status report is not a revocation.
+    pub fn exclude_revoked(&self) -> Vec<&FIDO2> {
+        self.filter_fido2(|fd| !fd.is_revoked())
+    }
+
+    /// Return the set of FIDO2 devices that are not revoked, and whose latest certification is
+    /// at least `level`.
+    pub fn min_certification_level(&self, level: &AuthenticatorStatus) -> Vec<&FIDO2> {
+        self.filter_fido2(|fd| {
+            !fd.is_revoked()
+                && fd
+                    .latest_certification()
+                    .map(|sr| sr.gte(level))
+                    .unwrap_or(false)
+        })
+    }
+
+    /// Return the set of FIDO2 devices that are able to perform user verification.
+    pub fn supporting_uv(&self) -> Vec<&FIDO2> {
+        self.filter_fido2(|fd| fd.supports_uv())
+    }
+
+    /// Return the set of FIDO2 devices that are able to satisfy a request where resident keys
+    /// are required.
+    pub fn requiring_rk(&self) -> Vec<&FIDO2> {
+        self.filter_fido2(|fd| fd.supports_rk())
+    }
+
     pub fn fido2_to_attestation_ca_list(fds: &[rc::Rc<FIDO2>]) -> Option<AttestationCaList> {
         let mut att_ca_builder = AttestationCaListBuilder::new();
 
@@ -1470,3 +1973,523 @@ impl FidoMds {
         Some(att_ca_builder.build())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use webauthn_attestation_ca::testkit::{generate_test_ca, issue_leaf_for};
+
+    const TEST_MDS: &str = include_str!("test_data/mds.json");
+
+    const TEST_KEY_UV: Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000001");
+    const TEST_KEY_PRESENCE: Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000002");
+    const TEST_KEY_REVOKED: Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000003");
+    const TEST_KEY_REINSTATED: Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000004");
+    const TEST_KEY_MULTI_ROOT: Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000006");
+    const TEST_KEY_REVOKED_NOTICE: Uuid = uuid::uuid!("a0000000-0000-0000-0000-000000000007");
+
+    fn test_mds() -> FidoMds {
+        let rawmds: RawFidoMds = serde_json::from_str(TEST_MDS).unwrap();
+        rawmds.into()
+    }
+
+    fn aaguids(fds: &[&FIDO2]) -> Vec<Uuid> {
+        fds.iter().map(|fd| fd.aaguid).collect()
+    }
+
+    fn sign_mds_jwt(
+        payload: &str,
+        chain: &[&x509::X509],
+        key: &openssl::pkey::PKeyRef<openssl::pkey::Private>,
+    ) -> String {
+        let x5c: Vec<String> = chain
+            .iter()
+            .map(|crt| STANDARD.encode(crt.to_der().unwrap()))
+            .collect();
+        let header = serde_json::json!({ "alg": "ES256", "typ": "JWT", "x5c": x5c });
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(payload)
+        );
+
+        // ES256 signatures are the raw r || s, not DER.
+        let digest = openssl::sha::sha256(signing_input.as_bytes());
+        let sig = openssl::ecdsa::EcdsaSig::sign(&digest, &key.ec_key().unwrap()).unwrap();
+        let mut raw_sig = sig.r().to_vec_padded(32).unwrap();
+        raw_sig.extend(sig.s().to_vec_padded(32).unwrap());
+
+        format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(raw_sig))
+    }
+
+    #[test]
+    fn test_from_str_verified() {
+        let (ca, ca_key) = generate_test_ca();
+        let (leaf, leaf_key) = issue_leaf_for(&ca, &ca_key, TEST_KEY_UV);
+        let jwt = sign_mds_jwt(TEST_MDS, &[&leaf], &leaf_key);
+
+        let rawmds = RawFidoMds::from_str_verified(&jwt, ca.ca()).unwrap();
+        assert_eq!(rawmds.next_update, "2099-01-01");
+        let mds: FidoMds = rawmds.into();
+        assert!(mds.with_aaguid(TEST_KEY_UV).is_some());
+
+        // The chain must lead to the pinned root.
+        let (other_ca, _) = generate_test_ca();
+        assert!(matches!(
+            RawFidoMds::from_str_verified(&jwt, other_ca.ca()),
+            Err(JwtError::X5cPublicKeyDenied)
+        ));
+
+        // A leaf from another CA is rejected, even though the CAs share a subject name.
+        let (forged_ca, forged_ca_key) = generate_test_ca();
+        let (forged_leaf, forged_leaf_key) =
+            issue_leaf_for(&forged_ca, &forged_ca_key, TEST_KEY_UV);
+        let forged = sign_mds_jwt(TEST_MDS, &[&forged_leaf], &forged_leaf_key);
+        assert!(matches!(
+            RawFidoMds::from_str_verified(&forged, ca.ca()),
+            Err(JwtError::X5cPublicKeyDenied)
+        ));
+
+        // A trusted chain does not vouch for a payload altered after signing.
+        let tampered_payload = URL_SAFE_NO_PAD.encode(TEST_MDS.replace("2099-01-01", "2099-12-31"));
+        let mut parts: Vec<&str> = jwt.split('.').collect();
+        parts[1] = &tampered_payload;
+        assert!(RawFidoMds::from_str_verified(&parts.join("."), ca.ca()).is_err());
+    }
+
+    #[test]
+    fn test_from_str_with_policy() {
+        // Add a device with a known uvm patch, whose uvm in the MDS no longer matches the
+        // uvm that the patch was written against.
+        const AUTHENTON1: Uuid = uuid::uuid!("b267239b-954f-4041-a01b-ee4f33c145b6");
+        let mut payload: serde_json::Value = serde_json::from_str(TEST_MDS).unwrap();
+        let entries = payload["entries"].as_array_mut().unwrap();
+        let mut entry = entries
+            .iter()
+            .find(|entry| entry["aaguid"] == TEST_KEY_PRESENCE.to_string())
+            .cloned()
+            .unwrap();
+        entry["aaguid"] = AUTHENTON1.to_string().into();
+        entry["metadataStatement"]["aaguid"] = AUTHENTON1.to_string().into();
+        entry["metadataStatement"]["authenticatorGetInfo"]["aaguid"] =
+            AUTHENTON1.to_string().into();
+        entries.push(entry);
+
+        let (ca, ca_key) = generate_test_ca();
+        let (leaf, leaf_key) = issue_leaf_for(&ca, &ca_key, TEST_KEY_UV);
+        let jwt = sign_mds_jwt(&payload.to_string(), &[&leaf], &leaf_key);
+
+        // Strict excludes the device but keeps the rest of the metadata.
+        let strict =
+            FidoMds::from_str_verified_with_policy(&jwt, ca.ca(), PatchPolicy::Strict).unwrap();
+        assert!(strict.with_aaguid(AUTHENTON1).is_none());
+        assert!(strict.with_aaguid(TEST_KEY_PRESENCE).is_some());
+
+        // Passthrough keeps the device, with the uvm from the MDS unpatched. This is also what
+        // the parsers without a policy do.
+        let unpatched = vec![
+            vec![UserVerificationMethod::PresenceInternal],
+            vec![UserVerificationMethod::None],
+        ];
+        let passthrough =
+            FidoMds::from_str_verified_with_policy(&jwt, ca.ca(), PatchPolicy::WarnAndPassthrough)
+                .unwrap();
+        let fd = passthrough.with_aaguid(AUTHENTON1).unwrap();
+        assert_eq!(fd.user_verification_details, unpatched);
+
+        let default = FidoMds::from_str_verified(&jwt, ca.ca()).unwrap();
+        let fd = default.with_aaguid(AUTHENTON1).unwrap();
+        assert_eq!(fd.user_verification_details, unpatched);
+
+        // from_str_with_policy verifies to the FIDO Alliance's root, not the test root.
+        assert!(matches!(
+            FidoMds::from_str_with_policy(&jwt, PatchPolicy::WarnAndPassthrough),
+            Err(JwtError::X5cPublicKeyDenied)
+        ));
+    }
+
+    #[test]
+    fn test_fido2_capability_query() {
+        let mds = test_mds();
+
+        let fd = mds.with_aaguid(TEST_KEY_UV).unwrap();
+        assert_eq!(fd.description, "Webauthn RS Test Key UV");
+        assert!(mds
+            .with_aaguid(uuid::uuid!("a0000000-0000-0000-0000-0000000000ff"))
+            .is_none());
+
+        assert_eq!(aaguids(&mds.supporting_uv()), vec![TEST_KEY_UV]);
+        assert_eq!(aaguids(&mds.requiring_rk()), vec![TEST_KEY_UV]);
+
+        let presence_only = mds.filter_fido2(|fd| !fd.supports_uv());
+        assert!(aaguids(&presence_only).contains(&TEST_KEY_PRESENCE));
+        assert!(!aaguids(&presence_only).contains(&TEST_KEY_UV));
+    }
+
+    #[test]
+    fn test_fido2_level_and_transport_query() {
+        let mds = test_mds();
+
+        let query = |q: &str| -> Vec<Uuid> {
+            let q = Query::from_str(q).unwrap();
+            mds.fido2_query(&q)
+                .map(|fds| fds.iter().map(|fd| fd.aaguid).collect())
+                .unwrap_or_default()
+        };
+
+        // The reinstated key is the only one certified at l2. Revoked keys hold no level, even
+        // though they were certified prior to revocation.
+        assert_eq!(query("level gte l2"), vec![TEST_KEY_REINSTATED]);
+        assert_eq!(query("level gt l1+"), vec![TEST_KEY_REINSTATED]);
+        assert_eq!(query("level eq l2"), vec![TEST_KEY_REINSTATED]);
+        assert_eq!(query("level lte l2"), query("level gte l1"));
+
+        let l1 = query("level lt l2");
+        assert_eq!(l1, query("level eq l1"));
+        assert!(l1.contains(&TEST_KEY_UV));
+        assert!(!l1.contains(&TEST_KEY_REVOKED));
+        assert!(!l1.contains(&TEST_KEY_REVOKED_NOTICE));
+        assert!(!l1.contains(&TEST_KEY_REINSTATED));
+        assert!(!query("level lte l3+").contains(&TEST_KEY_REVOKED));
+
+        assert!(query("level gte l3").is_empty());
+
+        assert_eq!(query("transport eq nfc"), vec![TEST_KEY_UV]);
+        assert!(query("level gte l2 and transport eq nfc").is_empty());
+        assert_eq!(query("level lt l2 and transport eq nfc"), vec![TEST_KEY_UV]);
+
+        // Devices without a certification level never match a level comparison.
+        let standalone =
+            FidoMds::from_metadata_statement(include_str!("test_data/metadata_statement.json"))
+                .unwrap();
+        assert!(!standalone.query_match(&Query::from_str("level lt l3+").unwrap()));
+        assert!(!standalone.query_match(&Query::from_str("level gte l1").unwrap()));
+    }
+
+    #[test]
+    fn test_u2f_parse() {
+        let mds = test_mds();
+
+        let u2f = mds
+            .u2f
+            .iter()
+            .find(|dev| {
+                dev.attestation_certificate_key_identifiers
+                    .contains(&"bf7bcaa0d0c6187a8c6abbdd16a15640e7c7bde2".to_string())
+            })
+            .unwrap();
+
+        assert_eq!(u2f.description, "Webauthn RS Test U2F Key");
+        assert_eq!(u2f.attestation_root_certificates.len(), 1);
+        assert!(x509::X509::from_der(&u2f.attestation_root_certificates[0]).is_ok());
+
+        // U2F devices must not be mixed into the FIDO2 set.
+        assert!(mds
+            .fido2
+            .iter()
+            .all(|fd| fd.description != "Webauthn RS Test U2F Key"));
+    }
+
+    #[test]
+    fn test_fido2_status_filters() {
+        let mds = test_mds();
+
+        let revoked = mds.with_aaguid(TEST_KEY_REVOKED).unwrap();
+        assert!(revoked.is_revoked());
+
+        // A later certification supersedes the earlier revocation.
+        let reinstated = mds.with_aaguid(TEST_KEY_REINSTATED).unwrap();
+        assert!(!reinstated.is_revoked());
+        assert!(reinstated
+            .latest_status_report()
+            .map(|sr| *sr == AuthenticatorStatus::FidoCertifiedL2)
+            .unwrap_or(false));
+
+        assert_eq!(
+            reinstated
+                .status_reports()
+                .iter()
+                .map(|sr| sr.effective_date())
+                .collect::<Vec<_>>(),
+            vec![Some("2022-06-01"), Some("2023-01-01")]
+        );
+        assert!(matches!(
+            reinstated.latest_certification(),
+            Some(StatusReport::FidoCertifiedL2 {
+                certification_descriptor: Some(desc),
+                url: Some(url),
+                ..
+            }) if desc == "Webauthn RS Test Key Reinstated"
+                && url == "https://example.com/certification/reinstated"
+        ));
+
+        // A later security or update notice does not lift a revocation.
+        let revoked_notice = mds.with_aaguid(TEST_KEY_REVOKED_NOTICE).unwrap();
+        assert!(revoked_notice
+            .latest_status_report()
+            .map(|sr| *sr == AuthenticatorStatus::UpdateAvailable)
+            .unwrap_or(false));
+        assert!(revoked_notice.is_revoked());
+
+        let not_revoked = aaguids(&mds.exclude_revoked());
+        assert!(!not_revoked.contains(&TEST_KEY_REVOKED));
+        assert!(!not_revoked.contains(&TEST_KEY_REVOKED_NOTICE));
+        assert!(not_revoked.contains(&TEST_KEY_REINSTATED));
+        assert!(not_revoked.contains(&TEST_KEY_UV));
+
+        assert_eq!(
+            aaguids(&mds.min_certification_level(&AuthenticatorStatus::FidoCertifiedL2)),
+            vec![TEST_KEY_REINSTATED]
+        );
+
+        let l1 = aaguids(&mds.min_certification_level(&AuthenticatorStatus::FidoCertifiedL1));
+        assert!(l1.contains(&TEST_KEY_UV));
+        assert!(l1.contains(&TEST_KEY_REINSTATED));
+        assert!(!l1.contains(&TEST_KEY_REVOKED));
+        assert!(!l1.contains(&TEST_KEY_REVOKED_NOTICE));
+    }
+
+    #[test]
+    fn test_status_report_same_date() {
+        let certified = StatusReport::FidoCertifiedL1 {
+            effective_date: Some("2023-01-01".to_string()),
+            authenticator_version: None,
+            certification_descriptor: None,
+            certificate_number: None,
+            certification_policy_version: None,
+            certification_requirements_version: None,
+            url: None,
+        };
+        let revoked = StatusReport::Revoked {
+            effective_date: Some("2023-01-01".to_string()),
+            authenticator_version: 1,
+            url: None,
+        };
+
+        // Both reports are retained, and the revocation is the latest.
+        let reports = BTreeSet::from([revoked.clone(), certified.clone()]);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports.first(), Some(&certified));
+        assert_eq!(reports.last(), Some(&revoked));
+    }
+
+    #[test]
+    fn test_fido2_transports_and_options() {
+        let mds = test_mds();
+
+        let fd = mds.with_aaguid(TEST_KEY_UV).unwrap();
+        assert_eq!(
+            fd.transports(),
+            &[AuthenticatorTransport::Usb, AuthenticatorTransport::Nfc]
+        );
+        assert_eq!(fd.options().get("uv"), Some(&true));
+        assert_eq!(fd.options().get("clientPin"), Some(&true));
+
+        let fd = mds.with_aaguid(TEST_KEY_PRESENCE).unwrap();
+        assert_eq!(fd.transports(), &[AuthenticatorTransport::Usb]);
+        assert_eq!(fd.options().get("rk"), Some(&false));
+
+        // U2F devices generally lack authenticatorGetInfo.
+        let u2f = mds
+            .u2f
+            .iter()
+            .find(|fd| fd.description == "Webauthn RS Test U2F Key")
+            .unwrap();
+        assert!(u2f.transports().is_empty());
+        assert!(u2f.options().is_empty());
+    }
+
+    #[test]
+    fn test_from_metadata_statement() {
+        let fd =
+            FidoMds::from_metadata_statement(include_str!("test_data/metadata_statement.json"))
+                .unwrap();
+
+        assert_eq!(
+            fd.aaguid,
+            uuid::uuid!("a0000000-0000-0000-0000-000000000005")
+        );
+        assert_eq!(fd.description, "Webauthn RS Test Standalone Key");
+        assert!(fd.supports_uv());
+        assert_eq!(fd.attestation_root_certificates.len(), 1);
+        assert!(fd.status_reports.is_empty());
+
+        let err = FidoMds::from_metadata_statement("{}").unwrap_err();
+        assert!(matches!(err, MdsError::Json(_)));
+        // Usable as a boxed error, with the json error as its source.
+        let err: Box<dyn std::error::Error> = Box::new(err);
+        assert!(err
+            .to_string()
+            .starts_with("invalid metadata statement json"));
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn test_from_vendor_metadata_statement() {
+        // A vendor statement from the FIDO MDS, to show that real files are accepted.
+        let fd = FidoMds::from_metadata_statement(include_str!(
+            "test_data/yubikey_5_nfc_statement.json"
+        ))
+        .unwrap();
+
+        assert_eq!(
+            fd.aaguid,
+            uuid::uuid!("2fc0579f-8113-47ea-b116-bb5a8db9202a")
+        );
+        assert_eq!(fd.description, "YubiKey 5 Series with NFC");
+        assert_eq!(fd.authenticator_version, 50200);
+        assert_eq!(fd.firmware_version(), Some(328706));
+        assert!(fd.supports_uv());
+        assert!(fd.supports_rk());
+        assert_eq!(
+            fd.transports(),
+            &[AuthenticatorTransport::Nfc, AuthenticatorTransport::Usb]
+        );
+        assert!(fd.uvm_capabilities().passcode);
+
+        let root = x509::X509::from_der(&fd.attestation_root_certificates[0]).unwrap();
+        let cn = root
+            .subject_name()
+            .entries_by_nid(openssl::nid::Nid::COMMONNAME)
+            .next()
+            .map(|entry| entry.data().as_utf8().unwrap().to_string());
+        assert_eq!(cn.as_deref(), Some("Yubico U2F Root CA Serial 457200631"));
+    }
+
+    #[test]
+    fn test_fido2_uvm_capabilities() {
+        let mds = test_mds();
+
+        // [[presence], [presence, passcode_external], [presence, fingerprint_internal]]
+        let fd = mds.with_aaguid(TEST_KEY_UV).unwrap();
+        assert_eq!(fd.user_verification_details.len(), 3);
+        assert_eq!(
+            fd.uvm_capabilities(),
+            UvmCapabilities {
+                presence: true,
+                passcode: true,
+                fingerprint: true,
+                ..Default::default()
+            }
+        );
+
+        // [[presence], [none]]
+        let fd = mds.with_aaguid(TEST_KEY_PRESENCE).unwrap();
+        assert_eq!(
+            fd.uvm_capabilities(),
+            UvmCapabilities {
+                presence: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_fido2_version_info() {
+        let mds = test_mds();
+
+        let fd = mds.with_aaguid(TEST_KEY_UV).unwrap();
+        assert_eq!(fd.authenticator_version, 2);
+        assert_eq!(fd.firmware_version(), Some(328966));
+        assert_eq!(
+            fd.upv,
+            vec![Upv { major: 1, minor: 1 }, Upv { major: 1, minor: 0 }]
+        );
+
+        let fd = mds.with_aaguid(TEST_KEY_PRESENCE).unwrap();
+        assert_eq!(fd.firmware_version(), None);
+        assert_eq!(fd.upv, vec![Upv { major: 1, minor: 0 }]);
+    }
+
+    #[test]
+    fn test_mds_staleness() {
+        let date = |s: &str| MdsDate::from_str(s).unwrap();
+
+        let mds = test_mds();
+        assert_eq!(mds.next_update().unwrap(), date("2099-01-01"));
+        assert!(!mds.is_stale(date("2026-10-15")).unwrap());
+        assert!(!mds.is_stale(date("2099-01-01")).unwrap());
+        assert!(mds.is_stale(date("2099-01-02")).unwrap());
+
+        let mut rawmds: RawFidoMds = serde_json::from_str(TEST_MDS).unwrap();
+        rawmds.next_update = "2020-06-01".to_string();
+        let mds: FidoMds = rawmds.into();
+        assert!(mds.is_stale(date("2026-10-15")).unwrap());
+
+        let mut rawmds: RawFidoMds = serde_json::from_str(TEST_MDS).unwrap();
+        rawmds.next_update = "June 2020".to_string();
+        let mds: FidoMds = rawmds.into();
+        assert!(matches!(mds.next_update(), Err(MdsError::InvalidDate(_))));
+        assert!(matches!(
+            mds.is_stale(date("2026-10-15")),
+            Err(MdsError::InvalidDate(_))
+        ));
+    }
+
+    #[test]
+    fn test_mds_date() {
+        for valid in ["2099-01-01", "2024-02-29", "2000-02-29", "2023-12-31"] {
+            assert_eq!(MdsDate::from_str(valid).unwrap().to_string(), valid);
+        }
+
+        // Dates that are not YYYY-MM-DD, or do not exist, are rejected.
+        for invalid in [
+            "",
+            "2099-1-02",
+            "2099-01-02T00:00:00Z",
+            "2099/01/02",
+            "2099-13-01",
+            "2099-00-01",
+            "2099-01-00",
+            "2099-02-31",
+            "2099-04-31",
+            "2023-02-29",
+            "1900-02-29",
+        ] {
+            assert!(matches!(
+                MdsDate::from_str(invalid),
+                Err(MdsError::InvalidDate(date)) if date == invalid
+            ));
+        }
+
+        let date = MdsDate::new(2099, 1, 2).unwrap();
+        assert_eq!((date.year(), date.month(), date.day()), (2099, 1, 2));
+        assert!(MdsDate::new(2099, 1, 1).unwrap() < date);
+        assert!(date < MdsDate::new(2099, 2, 1).unwrap());
+    }
+
+    #[test]
+    fn test_fido2_multi_root_attestation_ca_list() {
+        let mds = test_mds();
+
+        let fd = mds.with_aaguid(TEST_KEY_MULTI_ROOT).unwrap();
+        assert_eq!(fd.attestation_root_certificates.len(), 3);
+
+        let att_ca_list = FidoMds::fido2_to_attestation_ca_list(&mds.fido2).unwrap();
+
+        // Each root becomes its own CA, and the device is trusted under all of them.
+        assert_eq!(att_ca_list.len(), 3);
+        assert!(att_ca_list
+            .cas()
+            .values()
+            .all(|att_ca| att_ca.aaguids().contains_key(&TEST_KEY_MULTI_ROOT)));
+
+        // The device shares the first root with the other test keys, but is the only device
+        // under the remaining two.
+        let only_multi_root = att_ca_list
+            .cas()
+            .values()
+            .filter(|att_ca| att_ca.aaguids().len() == 1)
+            .count();
+        assert_eq!(only_multi_root, 2);
+
+        for root in fd.attestation_root_certificates.iter() {
+            let kid = x509::X509::from_der(root)
+                .unwrap()
+                .digest(openssl::hash::MessageDigest::sha256())
+                .unwrap();
+            assert!(att_ca_list.contains_kid(&kid));
+        }
+    }
+}
@@ -48,9 +48,9 @@ fn assume_true() -> bool {
     true
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields, rename_all = "camelCase")]
-/// Unclear
+/// A version of the authenticator protocol (UAF, U2F or CTAP) that the device supports.
 pub struct Upv {
     /// Major
     pub major: u16,
@@ -580,6 +580,13 @@ pub struct AuthenticatorGetInfo {
     pub vendor_prototype_config_commands: Vec<u32>,
 }
 
+impl AuthenticatorGetInfo {
+    /// The vendor specific firmware version of a "factory new" device, if reported.
+    pub fn firmware_version(&self) -> Option<u32> {
+        self.firmware_version
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields, rename_all = "camelCase")]
 /// A statement describing a device and it's associated properties.
@@ -1065,13 +1072,22 @@ impl FromStr for FidoMds {
     type Err = JwtError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // Setup the trusted CA store so that we can validate the authenticity of the MDS blob.
         let root_ca = x509::X509::from_pem(GLOBAL_SIGN_ROOT_CA_R3.as_bytes())
             .map_err(|_| JwtError::OpenSSLError)?;
 
+        FidoMds::from_str_verified(s, &root_ca)
+    }
+}
+
+impl FidoMds {
+    /// Parse the metadata jwt, verifying that the x5c chain in the jwt header is signed by
+    /// `root_ca`, and that the payload is signed by the leaf of that chain. The payload is only
+    /// parsed once this verification has succeeded.
+    pub fn from_str_verified(s: &str, root_ca: &x509::X509) -> Result<Self, JwtError> {
+        // Setup the trusted CA store so that we can validate the authenticity of the MDS blob.
         let mut ca_store = store::X509StoreBuilder::new().map_err(|_| JwtError::OpenSSLError)?;
         ca_store
-            .add_cert(root_ca)
+            .add_cert(root_ca.clone())
             .map_err(|_| JwtError::OpenSSLError)?;
 
         let ca_store = ca_store.build();
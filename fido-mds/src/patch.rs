@@ -8,6 +8,20 @@ use uuid::Uuid;
 use crc32c::Crc32cHasher;
 use std::hash::{Hash, Hasher};
 
+/// How to handle a device with a known uvm patch, where the uvm published in the MDS no longer
+/// matches the uvm that the patch was written against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PatchPolicy {
+    /// Reject the device, so that it is excluded from the parsed metadata until it is
+    /// inspected manually and the patch is updated.
+    #[default]
+    Strict,
+    /// Log a warning, and keep the device with the uvm from the MDS unpatched.
+    WarnAndPassthrough,
+}
+
+type UvmPatch = fn(&[Vec<UserVerificationMethod>]) -> Result<Vec<Vec<UserVerificationMethod>>, ()>;
+
 const YK5LIGHTNING: Uuid = uuid::uuid!("c5ef55ff-ad9a-4b9f-b580-adebafe026d0");
 const YK5LIGHTNING_HASH: u64 = 3670715709;
 
@@ -26,6 +40,38 @@ const VERIMARK_GUARD_FINGERPRINT_HASH: u64 = 3483018605;
 const AUTHENTON1: Uuid = uuid::uuid!("b267239b-954f-4041-a01b-ee4f33c145b6");
 const AUTHENTON1_HASH: u64 = 1117557365;
 
+/// The set of devices that have known-broken user verification methods in the MDS. Each entry
+/// is the aaguid of the device, the hash of the uvm that the patch was written against, and the
+/// function that rewrites it. To patch a new device, add an entry here.
+static UVM_PATCHES: &[(Uuid, u64, UvmPatch)] = &[
+    (
+        YK5LIGHTNING,
+        YK5LIGHTNING_HASH,
+        user_verification_method_yk5lightning,
+    ),
+    (RSADS100, RSADS100_HASH, user_verification_method_rsads100),
+    (
+        FIDO_KEYPASS_S3,
+        FIDO_KEYPASS_S3_HASH,
+        user_verification_method_fido_keypass_s3,
+    ),
+    (
+        VIVOKEY_APEX,
+        VIVOKEY_APEX_HASH,
+        user_verification_method_vivokey_apex,
+    ),
+    (
+        VERIMARK_GUARD_FINGERPRINT,
+        VERIMARK_GUARD_FINGERPRINT_HASH,
+        user_verification_method_verimark_guard_fingerprint,
+    ),
+    (
+        AUTHENTON1,
+        AUTHENTON1_HASH,
+        user_verification_method_authenton1,
+    ),
+];
+
 pub(crate) fn mds_user_verification_method_code_accuracy_descriptor(
     uvm: &mut [Vec<VerificationMethodAndCombinations>],
 ) -> bool {
@@ -74,82 +120,47 @@ pub(crate) fn mds_user_verification_method_invalid_all_present(
 }
 
 pub(crate) fn user_verification_method(
+    policy: PatchPolicy,
     aaguid: Option<Uuid>,
     uvm: &Vec<Vec<UserVerificationMethod>>,
 ) -> Result<Option<Vec<Vec<UserVerificationMethod>>>, ()> {
+    user_verification_method_with_patches(UVM_PATCHES, policy, aaguid, uvm)
+}
+
+fn user_verification_method_with_patches(
+    patches: &[(Uuid, u64, UvmPatch)],
+    policy: PatchPolicy,
+    aaguid: Option<Uuid>,
+    uvm: &[Vec<UserVerificationMethod>],
+) -> Result<Option<Vec<Vec<UserVerificationMethod>>>, ()> {
+    let Some(aaguid) = aaguid else {
+        return Ok(None);
+    };
+
     #[allow(deprecated)]
     let mut hasher = Crc32cHasher::default();
     uvm.hash(&mut hasher);
     let hash = hasher.finish();
 
-    match aaguid {
-        Some(aaguid) => {
-            if aaguid == YK5LIGHTNING {
-                if hash == YK5LIGHTNING_HASH {
-                    user_verification_method_yk5lightning(uvm).map(Some)
-                } else {
-                    warn!(
-                        "Hash for {} hash changed ({}), this must be inspected manually",
-                        YK5LIGHTNING, hash
-                    );
-                    Err(())
-                }
-            } else if aaguid == RSADS100 {
-                if hash == RSADS100_HASH {
-                    user_verification_method_rsads100(uvm).map(Some)
-                } else {
-                    warn!(
-                        "Hash for {} hash changed ({}), this must be inspected manually",
-                        RSADS100, hash
-                    );
-                    Err(())
-                }
-            } else if aaguid == FIDO_KEYPASS_S3 {
-                if hash == FIDO_KEYPASS_S3_HASH {
-                    user_verification_method_fido_keypass_s3(uvm).map(Some)
-                } else {
-                    warn!(
-                        "Hash for {} hash changed ({}), this must be inspected manually",
-                        FIDO_KEYPASS_S3, hash
-                    );
-                    Err(())
-                }
-            } else if aaguid == VIVOKEY_APEX {
-                if hash == VIVOKEY_APEX_HASH {
-                    user_verification_method_vivokey_apex(uvm).map(Some)
-                } else {
-                    warn!(
-                        "Hash for {} hash changed ({}), this must be inspected manually",
-                        VIVOKEY_APEX, hash
-                    );
-                    Err(())
-                }
-            } else if aaguid == VERIMARK_GUARD_FINGERPRINT {
-                if hash == VERIMARK_GUARD_FINGERPRINT_HASH {
-                    user_verification_method_verimark_guard_fingerprint(uvm).map(Some)
-                } else {
-                    warn!(
-                        "Hash for {} hash changed ({}), this must be inspected manually",
-                        VERIMARK_GUARD_FINGERPRINT, hash
-                    );
-                    Err(())
-                }
-            } else if aaguid == AUTHENTON1 {
-                if hash == AUTHENTON1_HASH {
-                    user_verification_method_authenton1(uvm).map(Some)
-                } else {
-                    warn!(
-                        "Hash for {} hash changed ({}), this must be inspected manually",
-                        AUTHENTON1, hash
-                    );
-                    Err(())
-                }
+    match patches.iter().find(|(p_aaguid, _, _)| *p_aaguid == aaguid) {
+        Some((_, p_hash, patch)) => {
+            if hash == *p_hash {
+                patch(uvm).map(Some)
             } else {
-                debug!(?hash);
-                Ok(None)
+                warn!(
+                    "Hash for {} hash changed ({}), this must be inspected manually",
+                    aaguid, hash
+                );
+                match policy {
+                    PatchPolicy::Strict => Err(()),
+                    PatchPolicy::WarnAndPassthrough => Ok(None),
+                }
             }
         }
-        None => Ok(None),
+        None => {
+            debug!(?hash);
+            Ok(None)
+        }
     }
 }
 
@@ -287,3 +298,119 @@ fn user_verification_method_authenton1(
         vec![UserVerificationMethod::None],
     ])
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mds::CodeAccuracyDescriptor;
+
+    fn yk5lightning_cad() -> Option<CodeAccuracyDescriptor> {
+        Some(CodeAccuracyDescriptor {
+            base: 64,
+            min_length: 4,
+            max_retries: Some(8),
+            block_slowdown: Some(0),
+        })
+    }
+
+    #[test]
+    fn test_patch_yk5lightning() {
+        // This is the uvm as published by FIDO, which matches YK5LIGHTNING_HASH.
+        let uvm = vec![vec![
+            UserVerificationMethod::PresenceInternal,
+            UserVerificationMethod::PasscodeInternal(yk5lightning_cad()),
+            UserVerificationMethod::None,
+        ]];
+
+        let patched = user_verification_method(PatchPolicy::Strict, Some(YK5LIGHTNING), &uvm);
+
+        assert_eq!(
+            patched,
+            Ok(Some(vec![
+                vec![UserVerificationMethod::PresenceInternal],
+                vec![
+                    UserVerificationMethod::PresenceInternal,
+                    UserVerificationMethod::PasscodeExternal(yk5lightning_cad()),
+                ],
+                vec![UserVerificationMethod::PasscodeExternal(yk5lightning_cad())],
+                vec![UserVerificationMethod::None],
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_patch_yk5lightning_hash_mismatch() {
+        // The max retries differ, so the hash no longer matches and this must be
+        // inspected manually rather than patched.
+        let uvm = vec![vec![
+            UserVerificationMethod::PresenceInternal,
+            UserVerificationMethod::PasscodeInternal(Some(CodeAccuracyDescriptor {
+                base: 64,
+                min_length: 4,
+                max_retries: Some(16),
+                block_slowdown: Some(0),
+            })),
+            UserVerificationMethod::None,
+        ]];
+
+        assert_eq!(
+            user_verification_method(PatchPolicy::Strict, Some(YK5LIGHTNING), &uvm),
+            Err(())
+        );
+
+        // When lenient, the uvm is passed through unpatched.
+        assert_eq!(
+            user_verification_method(PatchPolicy::WarnAndPassthrough, Some(YK5LIGHTNING), &uvm),
+            Ok(None)
+        );
+    }
+
+    fn fake_patch(
+        _uvm_and: &[Vec<UserVerificationMethod>],
+    ) -> Result<Vec<Vec<UserVerificationMethod>>, ()> {
+        Ok(vec![vec![UserVerificationMethod::PresenceInternal]])
+    }
+
+    #[test]
+    fn test_patch_registry() {
+        let fake = uuid::uuid!("00000000-0000-0000-0000-000000000001");
+        // [[None, PresenceInternal]]
+        let uvm = vec![vec![
+            UserVerificationMethod::None,
+            UserVerificationMethod::PresenceInternal,
+        ]];
+        let patches: &[(Uuid, u64, UvmPatch)] = &[(fake, 2407033003, fake_patch)];
+
+        assert_eq!(
+            user_verification_method_with_patches(patches, PatchPolicy::Strict, Some(fake), &uvm),
+            Ok(Some(vec![vec![UserVerificationMethod::PresenceInternal]]))
+        );
+
+        // Unregistered devices are not patched.
+        assert_eq!(
+            user_verification_method_with_patches(
+                patches,
+                PatchPolicy::Strict,
+                Some(YK5LIGHTNING),
+                &uvm
+            ),
+            Ok(None)
+        );
+        assert_eq!(
+            user_verification_method_with_patches(patches, PatchPolicy::Strict, None, &uvm),
+            Ok(None)
+        );
+
+        // A registered device whose hash changed must be inspected.
+        let changed = vec![vec![UserVerificationMethod::PresenceInternal]];
+        assert_eq!(
+            user_verification_method_with_patches(
+                patches,
+                PatchPolicy::Strict,
+                Some(fake),
+                &changed
+            ),
+            Err(())
+        );
+    }
+}
@@ -2,6 +2,8 @@
 //! based on the SCIM query language.
 //!
 //! `aaguid eq abcd and userverification eq passcodeexternal`
+//!
+//! `level gte l2 and transport eq nfc`
 
 use crate::{AuthenticatorStatus, AuthenticatorTransport, UserVerificationMethod};
 use std::str::FromStr;
@@ -15,6 +17,11 @@ pub enum AttrValueAssertion {
     StatusEq(AuthenticatorStatus),
     StatusGte(AuthenticatorStatus),
     StatusLt(AuthenticatorStatus),
+    LevelEq(AuthenticatorStatus),
+    LevelGt(AuthenticatorStatus),
+    LevelGte(AuthenticatorStatus),
+    LevelLt(AuthenticatorStatus),
+    LevelLte(AuthenticatorStatus),
     TransportEq(AuthenticatorTransport),
     UserVerificationCnt(UserVerificationMethod),
 }
@@ -81,6 +88,11 @@ peg::parser! {
             authstat_eq_expr() /
             authstat_gte_expr() /
             authstat_lt_expr() /
+            level_eq_expr() /
+            level_gte_expr() /
+            level_gt_expr() /
+            level_lte_expr() /
+            level_lt_expr() /
             authtrans_eq_expr() /
             uvm_cnt_expr()
 
@@ -102,6 +114,21 @@ peg::parser! {
         rule authstat_lt_expr() -> Query =
             "status" separator()+ "lt" separator()+ v:status() { Query::Op(AttrValueAssertion::StatusLt(v)) }
 
+        rule level_eq_expr() -> Query =
+            "level" separator()+ "eq" separator()+ v:level() { Query::Op(AttrValueAssertion::LevelEq(v)) }
+
+        rule level_gte_expr() -> Query =
+            "level" separator()+ "gte" separator()+ v:level() { Query::Op(AttrValueAssertion::LevelGte(v)) }
+
+        rule level_gt_expr() -> Query =
+            "level" separator()+ "gt" separator()+ v:level() { Query::Op(AttrValueAssertion::LevelGt(v)) }
+
+        rule level_lte_expr() -> Query =
+            "level" separator()+ "lte" separator()+ v:level() { Query::Op(AttrValueAssertion::LevelLte(v)) }
+
+        rule level_lt_expr() -> Query =
+            "level" separator()+ "lt" separator()+ v:level() { Query::Op(AttrValueAssertion::LevelLt(v)) }
+
         rule authtrans_eq_expr() -> Query =
             "transport" separator()+ "eq" separator()+ v:transport() { Query::Op(AttrValueAssertion::TransportEq(v)) }
 
@@ -114,6 +141,14 @@ peg::parser! {
         pub(crate) rule status() -> AuthenticatorStatus =
             s:$((!operator()[_])+) {? AuthenticatorStatus::from_str(s).map_err(|_| "invalid Authenticator Status" ) }
 
+        pub(crate) rule level() -> AuthenticatorStatus =
+            s:$((!operator()[_])+) {?
+                AuthenticatorStatus::from_str(s)
+                    .ok()
+                    .filter(|v| v.numeric() > 0)
+                    .ok_or("invalid Certification Level")
+            }
+
         pub(crate) rule transport() -> AuthenticatorTransport =
             s:$((!operator()[_])+) {? AuthenticatorTransport::from_str(s).map_err(|_| "invalid Authenticator Transport" ) }
 
@@ -157,6 +192,59 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_query_attr_level() {
+        assert_eq!(
+            query::expr("level gte l2"),
+            Ok(Query::Op(AttrValueAssertion::LevelGte(
+                AuthenticatorStatus::FidoCertifiedL2
+            )))
+        );
+        assert_eq!(
+            query::expr("level gt l1+"),
+            Ok(Query::Op(AttrValueAssertion::LevelGt(
+                AuthenticatorStatus::FidoCertifiedL1Plus
+            )))
+        );
+        assert_eq!(
+            query::expr("level lte l3"),
+            Ok(Query::Op(AttrValueAssertion::LevelLte(
+                AuthenticatorStatus::FidoCertifiedL3
+            )))
+        );
+        assert_eq!(
+            query::expr("level lt l2+"),
+            Ok(Query::Op(AttrValueAssertion::LevelLt(
+                AuthenticatorStatus::FidoCertifiedL2Plus
+            )))
+        );
+        assert_eq!(
+            query::expr("level eq l1"),
+            Ok(Query::Op(AttrValueAssertion::LevelEq(
+                AuthenticatorStatus::FidoCertifiedL1
+            )))
+        );
+        // Only certification levels can be compared.
+        assert!(query::expr("level gte revoked").is_err());
+        assert!(query::expr("level gte not-certified").is_err());
+        assert!(query::expr("level gte l4").is_err());
+    }
+
+    #[test]
+    fn test_query_level_and_transport() {
+        assert_eq!(
+            query::parse("level gte l2 and transport eq nfc"),
+            Ok(Query::And(
+                Box::new(Query::Op(AttrValueAssertion::LevelGte(
+                    AuthenticatorStatus::FidoCertifiedL2
+                ))),
+                Box::new(Query::Op(AttrValueAssertion::TransportEq(
+                    AuthenticatorTransport::Nfc
+                ))),
+            ))
+        );
+    }
+
     #[test]
     fn test_query_not() {
         assert_eq!(